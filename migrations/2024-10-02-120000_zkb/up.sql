@@ -0,0 +1,13 @@
+-- Your SQL goes here
+CREATE TABLE zkb(
+    killmail_id INTEGER NOT NULL PRIMARY KEY REFERENCES killmails(killmail_id) ON DELETE CASCADE ON UPDATE CASCADE,
+    hash TEXT NOT NULL,
+    total_value DOUBLE NOT NULL,
+    fitted_value DOUBLE NOT NULL,
+    dropped_value DOUBLE NOT NULL,
+    points INTEGER NOT NULL,
+    npc BOOLEAN NOT NULL CHECK (npc IN (0, 1)),
+    solo BOOLEAN NOT NULL CHECK (solo IN (0, 1)),
+    awox BOOLEAN NOT NULL CHECK (awox IN (0, 1)),
+    labels TEXT NOT NULL
+) WITHOUT ROWID;
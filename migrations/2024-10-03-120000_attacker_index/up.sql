@@ -0,0 +1,70 @@
+-- Your SQL goes here
+CREATE TABLE attackers_new(
+    killmail_id INTEGER NOT NULL REFERENCES killmails(killmail_id) ON DELETE CASCADE ON UPDATE CASCADE,
+    attacker_index INTEGER NOT NULL,
+    character_id INTEGER NOT NULL,
+    corporation_id INTEGER NOT NULL,
+    alliance_id INTEGER NOT NULL,
+    faction_id INTEGER NOT NULL,
+    damage_done INTEGER NOT NULL,
+    final_blow BOOLEAN NOT NULL CHECK (final_blow IN (0, 1)),
+    security_status REAL NOT NULL,
+    ship_type_id INTEGER NOT NULL,
+    weapon_type_id INTEGER NOT NULL,
+    PRIMARY KEY (
+        killmail_id,
+        attacker_index
+    )
+) WITHOUT ROWID;
+INSERT INTO attackers_new
+SELECT
+    killmail_id,
+    ROW_NUMBER() OVER (PARTITION BY killmail_id ORDER BY final_blow DESC, damage_done DESC) - 1,
+    character_id,
+    corporation_id,
+    alliance_id,
+    faction_id,
+    damage_done,
+    final_blow,
+    security_status,
+    ship_type_id,
+    weapon_type_id
+FROM attackers;
+DROP TABLE attackers;
+ALTER TABLE attackers_new RENAME TO attackers;
+CREATE INDEX attacker_character ON attackers (character_id ASC);
+CREATE INDEX attacker_corporation ON attackers (corporation_id ASC);
+CREATE INDEX attacker_alliance ON attackers (alliance_id ASC);
+CREATE INDEX attacker_faction ON attackers (faction_id ASC);
+
+CREATE TABLE victims_new(
+    killmail_id INTEGER NOT NULL PRIMARY KEY REFERENCES killmails(killmail_id) ON DELETE CASCADE ON UPDATE CASCADE,
+    character_id INTEGER NOT NULL,
+    corporation_id INTEGER NOT NULL,
+    alliance_id INTEGER NOT NULL,
+    faction_id INTEGER NOT NULL,
+    damage_taken INTEGER NOT NULL,
+    ship_type_id INTEGER NOT NULL,
+    position_x DOUBLE,
+    position_y DOUBLE,
+    position_z DOUBLE
+) WITHOUT ROWID;
+INSERT OR IGNORE INTO victims_new
+SELECT
+    killmail_id,
+    character_id,
+    corporation_id,
+    alliance_id,
+    faction_id,
+    damage_taken,
+    ship_type_id,
+    position_x,
+    position_y,
+    position_z
+FROM victims;
+DROP TABLE victims;
+ALTER TABLE victims_new RENAME TO victims;
+CREATE INDEX victim_character ON victims (character_id ASC);
+CREATE INDEX victim_corporation ON victims (corporation_id ASC);
+CREATE INDEX victim_alliance ON victims (alliance_id ASC);
+CREATE INDEX victim_faction ON victims (faction_id ASC);
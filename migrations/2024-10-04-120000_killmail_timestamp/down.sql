@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX killmail_timestamp;
+ALTER TABLE killmails DROP COLUMN killmail_timestamp;
@@ -0,0 +1,4 @@
+-- Your SQL goes here
+ALTER TABLE killmails ADD COLUMN killmail_timestamp BIGINT NOT NULL DEFAULT 0;
+UPDATE killmails SET killmail_timestamp = CAST(strftime('%s', killmail_time) AS BIGINT);
+CREATE INDEX killmail_timestamp ON killmails(killmail_timestamp);
@@ -0,0 +1,85 @@
+-- Your SQL goes here
+CREATE TABLE rollups(
+    entity_type INTEGER NOT NULL,
+    entity_id INTEGER NOT NULL,
+    day BIGINT NOT NULL,
+    kills INTEGER NOT NULL,
+    damage_done BIGINT NOT NULL,
+    losses INTEGER NOT NULL,
+    damage_taken BIGINT NOT NULL,
+    PRIMARY KEY (
+        entity_type,
+        entity_id,
+        day
+    )
+) WITHOUT ROWID;
+CREATE TABLE rollup_ships(
+    entity_type INTEGER NOT NULL,
+    entity_id INTEGER NOT NULL,
+    day BIGINT NOT NULL,
+    ship_type_id INTEGER NOT NULL,
+    kills INTEGER NOT NULL,
+    losses INTEGER NOT NULL,
+    PRIMARY KEY (
+        entity_type,
+        entity_id,
+        day,
+        ship_type_id
+    )
+) WITHOUT ROWID;
+CREATE TABLE rollup_systems(
+    entity_type INTEGER NOT NULL,
+    entity_id INTEGER NOT NULL,
+    day BIGINT NOT NULL,
+    solar_system_id INTEGER NOT NULL,
+    kills INTEGER NOT NULL,
+    losses INTEGER NOT NULL,
+    PRIMARY KEY (
+        entity_type,
+        entity_id,
+        day,
+        solar_system_id
+    )
+) WITHOUT ROWID;
+
+CREATE TEMP VIEW rollup_entries AS
+SELECT 1 AS entity_type, character_id AS entity_id, killmail_id, ship_type_id, 1 AS kills, damage_done, 0 AS losses, 0 AS damage_taken FROM attackers WHERE character_id <> 0
+UNION ALL SELECT 2, corporation_id, killmail_id, ship_type_id, 1, damage_done, 0, 0 FROM attackers WHERE corporation_id <> 0
+UNION ALL SELECT 3, alliance_id, killmail_id, ship_type_id, 1, damage_done, 0, 0 FROM attackers WHERE alliance_id <> 0
+UNION ALL SELECT 4, faction_id, killmail_id, ship_type_id, 1, damage_done, 0, 0 FROM attackers WHERE faction_id <> 0
+UNION ALL SELECT 1, character_id, killmail_id, ship_type_id, 0, 0, 1, damage_taken FROM victims WHERE character_id <> 0
+UNION ALL SELECT 2, corporation_id, killmail_id, ship_type_id, 0, 0, 1, damage_taken FROM victims WHERE corporation_id <> 0
+UNION ALL SELECT 3, alliance_id, killmail_id, ship_type_id, 0, 0, 1, damage_taken FROM victims WHERE alliance_id <> 0
+UNION ALL SELECT 4, faction_id, killmail_id, ship_type_id, 0, 0, 1, damage_taken FROM victims WHERE faction_id <> 0;
+INSERT INTO rollups
+SELECT
+    e.entity_type,
+    e.entity_id,
+    k.killmail_timestamp - k.killmail_timestamp % 86400,
+    SUM(e.kills),
+    SUM(e.damage_done),
+    SUM(e.losses),
+    SUM(e.damage_taken)
+FROM rollup_entries e JOIN killmails k ON k.killmail_id = e.killmail_id
+GROUP BY 1, 2, 3;
+INSERT INTO rollup_ships
+SELECT
+    e.entity_type,
+    e.entity_id,
+    k.killmail_timestamp - k.killmail_timestamp % 86400,
+    e.ship_type_id,
+    SUM(e.kills),
+    SUM(e.losses)
+FROM rollup_entries e JOIN killmails k ON k.killmail_id = e.killmail_id
+GROUP BY 1, 2, 3, 4;
+INSERT INTO rollup_systems
+SELECT
+    e.entity_type,
+    e.entity_id,
+    k.killmail_timestamp - k.killmail_timestamp % 86400,
+    k.solar_system_id,
+    SUM(e.kills),
+    SUM(e.losses)
+FROM rollup_entries e JOIN killmails k ON k.killmail_id = e.killmail_id
+GROUP BY 1, 2, 3, 4;
+DROP VIEW rollup_entries;
@@ -0,0 +1,8 @@
+-- Your SQL goes here
+CREATE TABLE names(
+    id INTEGER NOT NULL PRIMARY KEY,
+    category TEXT NOT NULL,
+    name TEXT NOT NULL,
+    fetched_at BIGINT NOT NULL
+) WITHOUT ROWID;
+CREATE INDEX name_fetched_at ON names(fetched_at);
@@ -0,0 +1,2 @@
+-- Your SQL goes here
+CREATE INDEX killmail_war_id ON killmails(war_id);
@@ -0,0 +1,8 @@
+-- Your SQL goes here
+CREATE TABLE war_sides(
+    war_id INTEGER NOT NULL,
+    entity_id INTEGER NOT NULL,
+    aggressor BOOLEAN NOT NULL CHECK (aggressor IN (0, 1)),
+    fetched_at BIGINT NOT NULL,
+    PRIMARY KEY (war_id, entity_id)
+) WITHOUT ROWID;
@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX item_type;
+DROP TABLE items;
+ALTER TABLE victims DROP COLUMN position_z;
+ALTER TABLE victims DROP COLUMN position_y;
+ALTER TABLE victims DROP COLUMN position_x;
@@ -0,0 +1,19 @@
+-- Your SQL goes here
+ALTER TABLE victims ADD COLUMN position_x DOUBLE PRECISION;
+ALTER TABLE victims ADD COLUMN position_y DOUBLE PRECISION;
+ALTER TABLE victims ADD COLUMN position_z DOUBLE PRECISION;
+CREATE TABLE items(
+    killmail_id INTEGER NOT NULL REFERENCES killmails(killmail_id) ON DELETE CASCADE ON UPDATE CASCADE,
+    item_index INTEGER NOT NULL,
+    parent_index INTEGER,
+    flag INTEGER NOT NULL,
+    item_type_id INTEGER NOT NULL,
+    quantity_destroyed BIGINT,
+    quantity_dropped BIGINT,
+    singleton INTEGER NOT NULL,
+    PRIMARY KEY (
+        killmail_id,
+        item_index
+    )
+);
+CREATE INDEX item_type ON items (item_type_id ASC);
@@ -0,0 +1,13 @@
+-- Your SQL goes here
+CREATE TABLE zkb(
+    killmail_id INTEGER NOT NULL PRIMARY KEY REFERENCES killmails(killmail_id) ON DELETE CASCADE ON UPDATE CASCADE,
+    hash TEXT NOT NULL,
+    total_value DOUBLE PRECISION NOT NULL,
+    fitted_value DOUBLE PRECISION NOT NULL,
+    dropped_value DOUBLE PRECISION NOT NULL,
+    points INTEGER NOT NULL,
+    npc BOOLEAN NOT NULL,
+    solo BOOLEAN NOT NULL,
+    awox BOOLEAN NOT NULL,
+    labels TEXT NOT NULL
+);
@@ -0,0 +1,15 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE victims DROP CONSTRAINT victims_pkey;
+ALTER TABLE victims ADD PRIMARY KEY (killmail_id, character_id, corporation_id, alliance_id, faction_id);
+
+DELETE FROM attackers a
+USING attackers b
+WHERE a.killmail_id = b.killmail_id
+    AND a.character_id = b.character_id
+    AND a.corporation_id = b.corporation_id
+    AND a.alliance_id = b.alliance_id
+    AND a.faction_id = b.faction_id
+    AND a.attacker_index > b.attacker_index;
+ALTER TABLE attackers DROP CONSTRAINT attackers_pkey;
+ALTER TABLE attackers DROP COLUMN attacker_index;
+ALTER TABLE attackers ADD PRIMARY KEY (killmail_id, character_id, corporation_id, alliance_id, faction_id);
@@ -0,0 +1,18 @@
+-- Your SQL goes here
+ALTER TABLE attackers ADD COLUMN attacker_index INTEGER;
+UPDATE attackers
+SET attacker_index = numbered.attacker_index
+FROM (
+    SELECT
+        ctid,
+        ROW_NUMBER() OVER (PARTITION BY killmail_id ORDER BY final_blow DESC, damage_done DESC) - 1 AS attacker_index
+    FROM attackers
+) AS numbered
+WHERE attackers.ctid = numbered.ctid;
+ALTER TABLE attackers ALTER COLUMN attacker_index SET NOT NULL;
+ALTER TABLE attackers DROP CONSTRAINT attackers_pkey;
+ALTER TABLE attackers ADD PRIMARY KEY (killmail_id, attacker_index);
+
+DELETE FROM victims a USING victims b WHERE a.killmail_id = b.killmail_id AND a.ctid > b.ctid;
+ALTER TABLE victims DROP CONSTRAINT victims_pkey;
+ALTER TABLE victims ADD PRIMARY KEY (killmail_id);
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE rollup_systems;
+DROP TABLE rollup_ships;
+DROP TABLE rollups;
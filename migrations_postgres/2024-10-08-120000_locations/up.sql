@@ -0,0 +1,6 @@
+-- Your SQL goes here
+CREATE TABLE locations(
+    system_id INTEGER NOT NULL PRIMARY KEY,
+    constellation_id INTEGER NOT NULL,
+    region_id INTEGER NOT NULL
+);
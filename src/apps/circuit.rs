@@ -0,0 +1,473 @@
+use anyhow::anyhow;
+use itertools::Itertools;
+
+use crate::apps::route::Route;
+use crate::apps::warp::Warp;
+use crate::apps::waypoint::WayPoint;
+use crate::universe;
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fmt;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Step {
+    Jump {
+        system_id: i32,
+        stargate: WayPoint,
+        destination: i32,
+    },
+    Warp {
+        system_id: i32,
+        from: WayPoint,
+        to: WayPoint,
+    },
+}
+impl Step {
+    pub fn system_id(&self) -> i32 {
+        match self {
+            Step::Jump { system_id, .. } => *system_id,
+            Step::Warp { system_id, .. } => *system_id,
+        }
+    }
+
+    pub fn distance(&self) -> f64 {
+        match self {
+            Step::Jump { .. } => 0.0,
+            Step::Warp { from, to, .. } => from.distance_to(to),
+        }
+    }
+}
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Step::Jump {
+                stargate,
+                destination,
+                ..
+            } => write!(f, "Jump {} -> {}", stargate, destination),
+            Step::Warp { to, .. } => {
+                write!(f, "Warp {} ({:.0} km)", to, self.distance() / 1_000.0)
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Itinerary {
+    pub systems: Vec<i32>,
+    pub steps: Vec<Step>,
+    /// The requested systems left out of the circuit because they are avoided.
+    pub skipped: Vec<i32>,
+}
+impl Itinerary {
+    pub fn jumps(&self) -> usize {
+        self.steps
+            .iter()
+            .filter(|step| matches!(step, Step::Jump { .. }))
+            .count()
+    }
+
+    pub fn distance(&self) -> f64 {
+        self.steps.iter().map(|step| step.distance()).sum()
+    }
+
+    pub fn time(&self, warp: &Warp) -> f64 {
+        self.steps
+            .iter()
+            .filter(|step| matches!(step, Step::Warp { .. }))
+            .map(|step| warp.time(step.distance()))
+            .sum()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Circuit {
+    jumps: HashMap<i32, HashMap<i32, WayPoint>>,
+    sites: HashMap<i32, Vec<WayPoint>>,
+    penalties: HashMap<i32, usize>,
+    avoided: HashSet<i32>,
+}
+impl Circuit {
+    pub fn new() -> Self {
+        Self {
+            jumps: HashMap::new(),
+            sites: HashMap::new(),
+            penalties: HashMap::new(),
+            avoided: HashSet::new(),
+        }
+    }
+
+    pub fn systems(&self) -> Vec<i32> {
+        self.jumps
+            .iter()
+            .flat_map(|(id, gates)| std::iter::once(*id).chain(gates.keys().cloned()))
+            .sorted()
+            .dedup()
+            .collect()
+    }
+
+    pub fn penalize(&mut self, system_id: i32, jumps: usize) {
+        self.penalties.insert(system_id, jumps);
+    }
+
+    pub fn avoid(&mut self, system_id: i32) {
+        self.avoided.insert(system_id);
+    }
+
+    pub fn add_stargate(&mut self, stargate: &universe::Stargate) {
+        let gate = WayPoint::new(stargate.stargate_id, &stargate.name, &stargate.position);
+        self.jumps
+            .entry(stargate.system_id)
+            .or_default()
+            .insert(stargate.destination.system_id, gate);
+    }
+
+    pub fn add_site(&mut self, system_id: i32, site: WayPoint) {
+        self.sites.entry(system_id).or_default().push(site);
+    }
+
+    pub fn stargate(&self, system_id: i32, destination: i32) -> Option<&WayPoint> {
+        self.jumps
+            .get(&system_id)
+            .and_then(|gates| gates.get(&destination))
+    }
+
+    pub fn path(&self, from: i32, to: i32) -> Option<Vec<i32>> {
+        let mut previous = HashMap::new();
+        let mut costs = HashMap::from([(from, 0)]);
+        let mut queue = BinaryHeap::from([Reverse((0, from))]);
+        while let Some(Reverse((cost, current))) = queue.pop() {
+            if current == to {
+                let mut path = vec![to];
+                let mut current = to;
+                while let Some(prev) = previous.get(&current) {
+                    path.push(*prev);
+                    current = *prev;
+                }
+                path.reverse();
+                return Some(path);
+            }
+            if costs.get(&current).is_some_and(|known| *known < cost) {
+                continue;
+            }
+            if let Some(gates) = self.jumps.get(&current) {
+                for next in gates.keys() {
+                    if self.avoided.contains(next) && *next != to {
+                        continue;
+                    }
+                    let cost = cost + self.cost(*next);
+                    if costs.get(next).is_none_or(|known| cost < *known) {
+                        costs.insert(*next, cost);
+                        previous.insert(*next, current);
+                        queue.push(Reverse((cost, *next)));
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    pub fn jumps_between(&self, from: i32, to: i32) -> Option<usize> {
+        self.path(from, to).map(|path| path.len() - 1)
+    }
+
+    fn cost(&self, system_id: i32) -> usize {
+        1 + self.penalties.get(&system_id).cloned().unwrap_or_default()
+    }
+
+    fn path_cost(&self, from: i32, to: i32) -> Option<usize> {
+        self.path(from, to)
+            .map(|path| path.into_iter().skip(1).map(|id| self.cost(id)).sum())
+    }
+
+    pub fn order(&self, systems: &[i32]) -> Vec<i32> {
+        let systems = systems
+            .iter()
+            .filter(|id| !self.avoided.contains(id))
+            .cloned()
+            .collect::<Vec<i32>>();
+        let mut distances = HashMap::new();
+        for (a, b) in systems.iter().tuple_combinations() {
+            let jumps = self.path_cost(*a, *b).unwrap_or(usize::MAX / systems.len());
+            distances.insert((*a, *b), jumps);
+            distances.insert((*b, *a), jumps);
+        }
+        let cost = |route: &[i32]| -> usize {
+            route
+                .iter()
+                .tuple_windows()
+                .map(|(a, b)| distances.get(&(*a, *b)).cloned().unwrap_or_default())
+                .sum()
+        };
+
+        if systems.len() < 9 {
+            systems
+                .iter()
+                .cloned()
+                .permutations(systems.len())
+                .min_by_key(|route| cost(route))
+                .unwrap_or_default()
+        } else {
+            systems
+                .iter()
+                .map(|start| {
+                    let mut route = vec![*start];
+                    let mut rest = systems
+                        .iter()
+                        .filter(|id| *id != start)
+                        .cloned()
+                        .collect::<Vec<i32>>();
+                    while let Some(current) = route.last().cloned() {
+                        if let Some((idx, _)) = rest.iter().enumerate().min_by_key(|(_, id)| {
+                            distances
+                                .get(&(current, **id))
+                                .cloned()
+                                .unwrap_or(usize::MAX)
+                        }) {
+                            route.push(rest.remove(idx));
+                        } else {
+                            break;
+                        }
+                    }
+                    route
+                })
+                .min_by_key(|route| cost(route))
+                .unwrap_or_default()
+        }
+    }
+
+    pub fn build(&self, systems: &[i32]) -> anyhow::Result<Itinerary> {
+        let ordered = self.order(systems);
+        let mut passage = Vec::new();
+        for (a, b) in ordered.iter().tuple_windows() {
+            let path = self
+                .path(*a, *b)
+                .ok_or(anyhow!("There is no stargate path from {a} to {b}"))?;
+            if passage.is_empty() {
+                passage.extend(path);
+            } else {
+                passage.extend(path.into_iter().skip(1));
+            }
+        }
+        if passage.is_empty() {
+            passage.extend(ordered.iter().cloned());
+        }
+
+        let targets = ordered.iter().cloned().collect::<HashSet<i32>>();
+        let mut visited = HashSet::new();
+        let mut steps = Vec::new();
+        for (idx, system_id) in passage.iter().enumerate() {
+            let arrival = idx
+                .checked_sub(1)
+                .and_then(|prev| self.stargate(*system_id, passage[prev]));
+            let departure = passage
+                .get(idx + 1)
+                .and_then(|next| self.stargate(*system_id, *next));
+
+            let sites = if targets.contains(system_id) && visited.insert(*system_id) {
+                self.sites.get(system_id).cloned().unwrap_or_default()
+            } else {
+                Vec::new()
+            };
+            steps.extend(self.system_steps(*system_id, arrival, departure, sites));
+
+            if let (Some(gate), Some(next)) = (departure, passage.get(idx + 1)) {
+                steps.push(Step::Jump {
+                    system_id: *system_id,
+                    stargate: gate.clone(),
+                    destination: *next,
+                });
+            }
+        }
+
+        let skipped = systems
+            .iter()
+            .filter(|id| self.avoided.contains(id))
+            .cloned()
+            .collect();
+        Ok(Itinerary {
+            systems: passage,
+            steps,
+            skipped,
+        })
+    }
+
+    fn system_steps(
+        &self,
+        system_id: i32,
+        arrival: Option<&WayPoint>,
+        departure: Option<&WayPoint>,
+        sites: Vec<WayPoint>,
+    ) -> Vec<Step> {
+        // The first system has no arrival gate, so its route is built backward from the departure gate
+        let (start, finish, backward) = match (arrival, departure) {
+            (Some(arrival), departure) => (arrival.clone(), departure.cloned(), false),
+            (None, Some(departure)) => (departure.clone(), None, true),
+            (None, None) => match sites.first() {
+                Some(site) => (site.clone(), None, false),
+                None => return Vec::new(),
+            },
+        };
+
+        let mut route = Route::new(start.clone());
+        for site in sites.into_iter().filter(|site| site.id != start.id) {
+            route.add(site);
+        }
+        if let Some(finish) = finish {
+            route.set_arrival(finish);
+        }
+        route.build_best();
+
+        let mut points = route
+            .complete()
+            .iter()
+            .filter_map(|id| route.get(id))
+            .cloned()
+            .collect::<Vec<WayPoint>>();
+        if backward {
+            points.reverse();
+        }
+
+        points
+            .into_iter()
+            .tuple_windows()
+            .map(|(from, to)| Step::Warp {
+                system_id,
+                from,
+                to,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::Position;
+    use crate::universe::{Stargate, StargateDestination};
+
+    fn stargate(id: i32, system_id: i32, destination: i32, x: f64) -> Stargate {
+        Stargate {
+            stargate_id: id,
+            name: format!("Stargate ({destination})"),
+            position: Position::new(x, 0.0, 0.0),
+            destination: StargateDestination {
+                stargate_id: 0,
+                system_id: destination,
+            },
+            system_id,
+            type_id: 0,
+        }
+    }
+
+    fn site(id: i32, x: f64) -> WayPoint {
+        WayPoint::new(id, &format!("Belt {id}"), &Position::new(x, 0.0, 0.0))
+    }
+
+    // 1 - 2 - 3 - 4
+    fn chain() -> Circuit {
+        let mut circuit = Circuit::new();
+        circuit.add_stargate(&stargate(12, 1, 2, 100.0));
+        circuit.add_stargate(&stargate(21, 2, 1, -100.0));
+        circuit.add_stargate(&stargate(23, 2, 3, 100.0));
+        circuit.add_stargate(&stargate(32, 3, 2, -100.0));
+        circuit.add_stargate(&stargate(34, 3, 4, 100.0));
+        circuit.add_stargate(&stargate(43, 4, 3, -100.0));
+        circuit
+    }
+
+    #[test]
+    fn path() {
+        let circuit = chain();
+        assert_eq!(circuit.path(1, 4), Some(vec![1, 2, 3, 4]));
+        assert_eq!(circuit.path(3, 1), Some(vec![3, 2, 1]));
+        assert_eq!(circuit.path(1, 5), None);
+        assert_eq!(circuit.jumps_between(2, 2), Some(0));
+    }
+
+    //   2
+    // 1   4
+    //   3
+    fn diamond() -> Circuit {
+        let mut circuit = Circuit::new();
+        for (a, b) in [(1, 2), (1, 3), (2, 4), (3, 4)] {
+            circuit.add_stargate(&stargate(10 * a + b, a, b, 100.0));
+            circuit.add_stargate(&stargate(10 * b + a, b, a, -100.0));
+        }
+        circuit
+    }
+
+    #[test]
+    fn penalize() {
+        let mut circuit = diamond();
+        assert_eq!(circuit.path(1, 4), Some(vec![1, 2, 4]));
+
+        circuit.penalize(2, 5);
+        assert_eq!(circuit.path(1, 4), Some(vec![1, 3, 4]));
+        assert_eq!(circuit.jumps_between(1, 4), Some(2));
+    }
+
+    #[test]
+    fn avoid() {
+        let mut circuit = diamond();
+        circuit.penalize(2, 5);
+        circuit.avoid(3);
+        assert_eq!(circuit.path(1, 4), Some(vec![1, 2, 4]));
+        assert_eq!(circuit.path(1, 3), Some(vec![1, 3]));
+        assert_eq!(circuit.order(&[1, 3, 4]), vec![1, 4]);
+        assert_eq!(circuit.systems(), vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn order() {
+        let circuit = chain();
+        let order = circuit.order(&[3, 1, 4]);
+        assert!(order == vec![1, 3, 4] || order == vec![4, 3, 1]);
+    }
+
+    #[test]
+    fn build() -> anyhow::Result<()> {
+        let mut circuit = chain();
+        circuit.add_site(1, site(101, 0.0));
+        circuit.add_site(3, site(301, 50.0));
+        circuit.add_site(3, site(302, -50.0));
+
+        let itinerary = circuit.build(&[1, 3])?;
+        assert_eq!(itinerary.systems, vec![1, 2, 3]);
+        assert_eq!(itinerary.jumps(), 2);
+
+        let visited = itinerary
+            .steps
+            .iter()
+            .map(|step| match step {
+                Step::Jump { stargate, .. } => stargate.id,
+                Step::Warp { to, .. } => to.id,
+            })
+            .collect::<Vec<i32>>();
+        assert_eq!(visited, vec![12, 12, 23, 23, 302, 301]);
+        assert_eq!(itinerary.distance(), 100.0 + 200.0 + 50.0 + 100.0);
+        Ok(())
+    }
+
+    #[test]
+    fn build_unreachable() {
+        let mut circuit = chain();
+        circuit.add_stargate(&stargate(56, 5, 6, 100.0));
+        circuit.add_stargate(&stargate(65, 6, 5, -100.0));
+        assert!(circuit.build(&[1, 5]).is_err());
+    }
+
+    #[test]
+    fn build_avoided() -> anyhow::Result<()> {
+        let mut circuit = chain();
+        circuit.avoid(4);
+
+        let itinerary = circuit.build(&[1, 3, 4])?;
+        assert_eq!(itinerary.systems, vec![1, 2, 3]);
+        assert_eq!(itinerary.skipped, vec![4]);
+        Ok(())
+    }
+}
@@ -0,0 +1,118 @@
+use crate::apps::waypoint::WayPoint;
+use crate::common::Position;
+
+use std::collections::HashMap;
+use std::fmt;
+
+pub const ON_GRID: f64 = 1_000_000.0;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Kill {
+    pub killmail_id: i32,
+    pub position: Option<Position>,
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Danger {
+    hours: u16,
+    kills: HashMap<i32, Vec<Kill>>,
+}
+impl Danger {
+    pub fn new(hours: u16) -> Self {
+        Self {
+            hours,
+            kills: HashMap::new(),
+        }
+    }
+
+    pub fn hours(&self) -> u16 {
+        self.hours
+    }
+
+    pub fn add(&mut self, system_id: i32, killmail_id: i32, position: Option<Position>) {
+        self.kills.entry(system_id).or_default().push(Kill {
+            killmail_id,
+            position,
+        });
+    }
+
+    pub fn kills(&self, system_id: i32) -> usize {
+        self.kills
+            .get(&system_id)
+            .map(|v| v.len())
+            .unwrap_or_default()
+    }
+
+    pub fn kills_near(&self, system_id: i32, waypoint: &WayPoint, radius: f64) -> usize {
+        self.kills
+            .get(&system_id)
+            .map(|kills| {
+                kills
+                    .iter()
+                    .filter_map(|kill| kill.position.as_ref())
+                    .filter(|position| position.distance_to(&waypoint.position) <= radius)
+                    .count()
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn hot(&self, threshold: usize) -> Vec<i32> {
+        let mut systems = self
+            .kills
+            .iter()
+            .filter(|(_, kills)| kills.len() >= threshold)
+            .map(|(id, _)| *id)
+            .collect::<Vec<i32>>();
+        systems.sort();
+        systems
+    }
+}
+impl fmt::Display for Danger {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut systems = self.kills.keys().collect::<Vec<&i32>>();
+        systems.sort_by_key(|id| std::cmp::Reverse(self.kills(**id)));
+        for id in systems {
+            writeln!(
+                f,
+                "{}: {} kills in {} hours",
+                id,
+                self.kills(*id),
+                self.hours
+            )?;
+        }
+        write!(f, "")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kills() {
+        let mut danger = Danger::new(24);
+        danger.add(1, 100, None);
+        danger.add(1, 101, None);
+        danger.add(2, 200, None);
+
+        assert_eq!(danger.kills(1), 2);
+        assert_eq!(danger.kills(2), 1);
+        assert_eq!(danger.kills(3), 0);
+        assert_eq!(danger.hot(2), vec![1]);
+        assert_eq!(danger.hot(1), vec![1, 2]);
+    }
+
+    #[test]
+    fn kills_near() {
+        let mut danger = Danger::new(24);
+        danger.add(1, 100, Some(Position::new(1_000.0, 0.0, 0.0)));
+        danger.add(1, 101, Some(Position::new(1.0e12, 0.0, 0.0)));
+        danger.add(1, 102, None);
+        danger.add(2, 200, Some(Position::zero()));
+
+        let belt = WayPoint::new(10, "Belt", &Position::zero());
+        assert_eq!(danger.kills_near(1, &belt, ON_GRID), 1);
+        assert_eq!(danger.kills_near(2, &belt, ON_GRID), 1);
+        assert_eq!(danger.kills_near(3, &belt, ON_GRID), 0);
+    }
+}
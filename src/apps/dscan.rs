@@ -0,0 +1,205 @@
+use crate::apps::waypoint::WayPoint;
+use crate::common::units::AU;
+use crate::common::Position;
+
+pub const DSCAN_RANGE: f64 = 14.3 * AU;
+
+#[derive(Debug, PartialEq, Clone)]
+struct Node {
+    point: WayPoint,
+    axis: usize,
+    left: Option<Box<Node>>,
+    right: Option<Box<Node>>,
+}
+
+fn coordinate(position: &Position, axis: usize) -> f64 {
+    match axis {
+        0 => position.x,
+        1 => position.y,
+        _ => position.z,
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct SpatialIndex {
+    root: Option<Box<Node>>,
+    len: usize,
+}
+impl SpatialIndex {
+    pub fn new(points: Vec<WayPoint>) -> Self {
+        let len = points.len();
+        Self {
+            root: Self::build(points, 0),
+            len,
+        }
+    }
+
+    fn build(mut points: Vec<WayPoint>, depth: usize) -> Option<Box<Node>> {
+        if points.is_empty() {
+            return None;
+        }
+        let axis = depth % 3;
+        points.sort_by(|a, b| {
+            coordinate(&a.position, axis).total_cmp(&coordinate(&b.position, axis))
+        });
+        let right = points.split_off(points.len() / 2 + 1);
+        let point = points.pop()?;
+        Some(Box::new(Node {
+            point,
+            axis,
+            left: Self::build(points, depth + 1),
+            right: Self::build(right, depth + 1),
+        }))
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn range(&self, center: &Position, radius: f64) -> Vec<(&WayPoint, f64)> {
+        let mut found = Vec::new();
+        Self::range_impl(self.root.as_deref(), center, radius, &mut found);
+        found.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.id.cmp(&b.0.id)));
+        found
+    }
+
+    fn range_impl<'a>(
+        node: Option<&'a Node>,
+        center: &Position,
+        radius: f64,
+        found: &mut Vec<(&'a WayPoint, f64)>,
+    ) {
+        if let Some(node) = node {
+            let distance = node.point.position.distance_to(center);
+            if distance <= radius {
+                found.push((&node.point, distance));
+            }
+            let delta = coordinate(center, node.axis) - coordinate(&node.point.position, node.axis);
+            if delta <= radius {
+                Self::range_impl(node.left.as_deref(), center, radius, found);
+            }
+            if -delta <= radius {
+                Self::range_impl(node.right.as_deref(), center, radius, found);
+            }
+        }
+    }
+
+    pub fn nearest(&self, center: &Position, k: usize) -> Vec<(&WayPoint, f64)> {
+        let mut found = Vec::new();
+        if k > 0 {
+            Self::nearest_impl(self.root.as_deref(), center, k, &mut found);
+        }
+        found
+    }
+
+    fn nearest_impl<'a>(
+        node: Option<&'a Node>,
+        center: &Position,
+        k: usize,
+        found: &mut Vec<(&'a WayPoint, f64)>,
+    ) {
+        if let Some(node) = node {
+            let distance = node.point.position.distance_to(center);
+            let idx = found.partition_point(|(_, d)| *d <= distance);
+            if idx < k {
+                found.insert(idx, (&node.point, distance));
+                found.truncate(k);
+            }
+
+            let delta = coordinate(center, node.axis) - coordinate(&node.point.position, node.axis);
+            let (near, far) = if delta <= 0.0 {
+                (node.left.as_deref(), node.right.as_deref())
+            } else {
+                (node.right.as_deref(), node.left.as_deref())
+            };
+            Self::nearest_impl(near, center, k, found);
+            let worst = found.last().map(|(_, d)| *d).unwrap_or(f64::MAX);
+            if found.len() < k || delta.abs() <= worst {
+                Self::nearest_impl(far, center, k, found);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::units::from_au;
+
+    fn celestials() -> Vec<WayPoint> {
+        (0..50)
+            .map(|i| {
+                let f = i as f64;
+                WayPoint::new(
+                    i,
+                    &format!("Celestial {i}"),
+                    &Position::new(from_au(f), from_au((f * 7.0) % 13.0), from_au(-(f % 5.0))),
+                )
+            })
+            .collect()
+    }
+
+    fn brute_force(points: &[WayPoint], center: &Position) -> Vec<(i32, f64)> {
+        let mut all = points
+            .iter()
+            .map(|wp| (wp.id, wp.position.distance_to(center)))
+            .collect::<Vec<(i32, f64)>>();
+        all.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)));
+        all
+    }
+
+    #[test]
+    fn range() {
+        let points = celestials();
+        let index = SpatialIndex::new(points.clone());
+        assert_eq!(index.len(), 50);
+
+        let center = Position::new(from_au(20.0), from_au(5.0), 0.0);
+        let found = index
+            .range(&center, DSCAN_RANGE)
+            .into_iter()
+            .map(|(wp, d)| (wp.id, d))
+            .collect::<Vec<(i32, f64)>>();
+        let expected = brute_force(&points, &center)
+            .into_iter()
+            .filter(|(_, d)| *d <= DSCAN_RANGE)
+            .collect::<Vec<(i32, f64)>>();
+
+        assert!(!found.is_empty());
+        assert_eq!(found, expected);
+    }
+
+    #[test]
+    fn nearest() {
+        let points = celestials();
+        let index = SpatialIndex::new(points.clone());
+
+        let center = Position::new(from_au(33.3), from_au(-2.0), from_au(1.0));
+        let found = index
+            .nearest(&center, 5)
+            .into_iter()
+            .map(|(_, d)| d)
+            .collect::<Vec<f64>>();
+        let expected = brute_force(&points, &center)
+            .into_iter()
+            .take(5)
+            .map(|(_, d)| d)
+            .collect::<Vec<f64>>();
+
+        assert_eq!(found, expected);
+        assert!(index.nearest(&center, 0).is_empty());
+        assert_eq!(index.nearest(&center, 100).len(), 50);
+    }
+
+    #[test]
+    fn empty() {
+        let index = SpatialIndex::new(Vec::new());
+        assert!(index.is_empty());
+        assert!(index.range(&Position::zero(), DSCAN_RANGE).is_empty());
+        assert!(index.nearest(&Position::zero(), 3).is_empty());
+    }
+}
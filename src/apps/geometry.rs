@@ -0,0 +1,206 @@
+use crate::apps::waypoint::WayPoint;
+use crate::common::units::AU;
+use crate::common::Position;
+use crate::common::Vector;
+
+use itertools::Itertools;
+use std::fmt;
+
+pub const PERCH_MIN: f64 = 150_000.0;
+pub const PERCH_MAX: f64 = 200_000.0;
+
+const FRACTIONS: [f64; 7] = [0.25, 0.5, 0.75, 1.25, 1.5, 2.0, 3.0];
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct SafeSpot {
+    pub position: Position,
+    pub from: WayPoint,
+    pub to: WayPoint,
+    pub k: f64,
+    pub clearance: f64,
+}
+impl fmt::Display for SafeSpot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} -> {} x{:.2}: {} ({:.2} AU off)",
+            self.from.name,
+            self.to.name,
+            self.k,
+            self.position,
+            self.clearance / AU
+        )
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Perch {
+    pub position: Position,
+    pub stargate: WayPoint,
+    pub target: WayPoint,
+    pub distance: f64,
+}
+impl fmt::Display for Perch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {:.0} km aligned to {}: {}",
+            self.stargate.name,
+            self.distance / 1_000.0,
+            self.target.name,
+            self.position
+        )
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Geometry {
+    celestials: Vec<WayPoint>,
+}
+impl Geometry {
+    pub fn new(celestials: Vec<WayPoint>) -> Self {
+        Self { celestials }
+    }
+
+    pub fn celestials(&self) -> &Vec<WayPoint> {
+        &self.celestials
+    }
+
+    pub fn get(&self, id: i32) -> Option<&WayPoint> {
+        self.celestials.iter().find(|wp| wp.id == id)
+    }
+
+    pub fn clearance(&self, position: &Position) -> f64 {
+        self.celestials
+            .iter()
+            .map(|wp| wp.position.distance_to(position))
+            .fold(f64::MAX, f64::min)
+    }
+
+    pub fn safe_spots(&self, grids: &[i32], clearance: f64, range: f64) -> Vec<SafeSpot> {
+        let grids = self
+            .celestials
+            .iter()
+            .filter(|wp| grids.contains(&wp.id))
+            .collect::<Vec<&WayPoint>>();
+
+        let mut candidates = Vec::new();
+        for (from, to) in self.celestials.iter().tuple_combinations() {
+            for (from, to) in [(from, to), (to, from)] {
+                let vector = Vector::new(from.position.clone(), to.position.clone());
+                for k in FRACTIONS {
+                    let position = vector.point_at(k);
+                    let off_scan = grids
+                        .iter()
+                        .all(|grid| grid.position.distance_to(&position) > range);
+                    let off_grid = self.clearance(&position);
+                    if off_scan && off_grid > clearance {
+                        candidates.push(SafeSpot {
+                            position,
+                            from: from.clone(),
+                            to: to.clone(),
+                            k,
+                            clearance: off_grid,
+                        });
+                    }
+                }
+            }
+        }
+        candidates.sort_by(|a, b| b.clearance.total_cmp(&a.clearance));
+
+        let mut spots: Vec<SafeSpot> = Vec::new();
+        for candidate in candidates {
+            let unique = spots
+                .iter()
+                .all(|spot| spot.position.distance_to(&candidate.position) > clearance);
+            if unique {
+                spots.push(candidate);
+            }
+        }
+        spots
+    }
+
+    pub fn perches(&self, stargate: &WayPoint, distance: f64) -> Vec<Perch> {
+        self.celestials
+            .iter()
+            .filter(|target| target.id != stargate.id)
+            .map(|target| {
+                let vector = Vector::new(stargate.position.clone(), target.position.clone());
+                Perch {
+                    position: vector.point_at_distance(distance),
+                    stargate: stargate.clone(),
+                    target: target.clone(),
+                    distance,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apps::dscan::DSCAN_RANGE;
+    use approx::assert_relative_eq;
+
+    fn celestial(id: i32, x: f64, y: f64) -> WayPoint {
+        WayPoint::new(id, &format!("Celestial {id}"), &Position::new(x, y, 0.0))
+    }
+
+    fn geometry() -> Geometry {
+        Geometry::new(vec![
+            celestial(1, 0.0, 0.0),
+            celestial(2, 10.0 * AU, 0.0),
+            celestial(3, 0.0, 40.0 * AU),
+        ])
+    }
+
+    #[test]
+    fn clearance() {
+        let geometry = geometry();
+        assert_relative_eq!(
+            geometry.clearance(&Position::new(5.0 * AU, 0.0, 0.0)),
+            5.0 * AU
+        );
+        assert_relative_eq!(geometry.clearance(&Position::new(0.0, 0.0, 0.0)), 0.0);
+    }
+
+    #[test]
+    fn safe_spots() {
+        let geometry = geometry();
+        let spots = geometry.safe_spots(&[1], 2.0 * AU, DSCAN_RANGE);
+
+        assert!(!spots.is_empty());
+        for spot in &spots {
+            assert!(geometry.clearance(&spot.position) > 2.0 * AU);
+            assert!(spot.position.distance_to(&Position::zero()) > DSCAN_RANGE);
+        }
+        for (a, b) in spots.iter().tuple_combinations() {
+            assert!(a.position.distance_to(&b.position) > 2.0 * AU);
+        }
+        assert!(spots
+            .windows(2)
+            .all(|pair| pair[0].clearance >= pair[1].clearance));
+    }
+
+    #[test]
+    fn safe_spots_too_close() {
+        let geometry = geometry();
+        assert!(geometry
+            .safe_spots(&[1, 2, 3], 2.0 * AU, 100.0 * AU)
+            .is_empty());
+    }
+
+    #[test]
+    fn perches() {
+        let geometry = geometry();
+        let stargate = geometry.get(1).cloned().unwrap();
+        let perches = geometry.perches(&stargate, PERCH_MIN);
+
+        assert_eq!(perches.len(), 2);
+        assert_eq!(perches[0].target.id, 2);
+        assert_eq!(perches[0].position, Position::new(PERCH_MIN, 0.0, 0.0));
+        assert_eq!(perches[1].target.id, 3);
+        assert_eq!(perches[1].position, Position::new(0.0, PERCH_MIN, 0.0));
+    }
+}
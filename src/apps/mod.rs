@@ -7,10 +7,21 @@ use std::collections::HashMap;
 use std::collections::HashSet;
 use std::collections::LinkedList;
 
+pub mod circuit;
+pub mod danger;
+pub mod dscan;
+pub mod geometry;
 pub mod route;
+pub mod warp;
 pub mod waypoint;
 
+pub use circuit::Circuit;
+pub use circuit::Itinerary;
+pub use danger::Danger;
+pub use dscan::SpatialIndex;
+pub use geometry::Geometry;
 pub use route::Route;
+pub use warp::Warp;
 pub use waypoint::WayPoint;
 
 #[derive(Debug, PartialEq, Clone, Default)]
@@ -1,5 +1,6 @@
 use itertools::Itertools;
 
+use crate::apps::warp::Warp;
 use crate::apps::waypoint::WayPoint;
 use std::collections::HashMap;
 use std::collections::HashSet;
@@ -9,6 +10,7 @@ use std::mem::swap;
 #[derive(Debug, Clone)]
 pub struct Route {
     start: WayPoint,
+    arrival: Option<WayPoint>,
     waypoints: HashMap<i32, WayPoint>,
     order: Vec<i32>,
 }
@@ -16,6 +18,7 @@ impl Route {
     pub fn new(start: WayPoint) -> Self {
         Self {
             start: start,
+            arrival: None,
             waypoints: HashMap::new(),
             order: Vec::new(),
         }
@@ -35,10 +38,21 @@ impl Route {
         self.start = start;
     }
 
+    pub fn arrival(&self) -> Option<&WayPoint> {
+        self.arrival.as_ref()
+    }
+
+    pub fn set_arrival(&mut self, arrival: WayPoint) {
+        self.arrival = Some(arrival);
+    }
+
     pub fn get(&self, id: &i32) -> Option<&WayPoint> {
         if self.start.id.eq(id) {
             return Some(&self.start);
         }
+        if let Some(arrival) = self.arrival.as_ref().filter(|wp| wp.id.eq(id)) {
+            return Some(arrival);
+        }
         self.waypoints.get(id)
     }
 
@@ -49,6 +63,7 @@ impl Route {
     pub fn complete(&self) -> Vec<i32> {
         std::iter::once(self.start.id)
             .chain(self.order.iter().cloned())
+            .chain(self.arrival.iter().map(|wp| wp.id))
             .collect()
     }
 
@@ -56,6 +71,21 @@ impl Route {
         self.route_length(&self.order)
     }
 
+    pub fn legs(&self) -> Vec<(&WayPoint, &WayPoint)> {
+        self.complete()
+            .iter()
+            .filter_map(|id| self.get(id))
+            .tuple_windows()
+            .collect()
+    }
+
+    pub fn time(&self, warp: &Warp) -> f64 {
+        self.legs()
+            .into_iter()
+            .map(|(a, b)| warp.time(a.distance_to(b)))
+            .sum()
+    }
+
     pub fn build_best(&mut self) {
         if self.order().len() < 10 {
             self.find_route_bruteforce()
@@ -64,20 +94,43 @@ impl Route {
         }
     }
 
-    fn route_length(&self, route: &Vec<i32>) -> f64 {
-        let mut distance = 0.0;
-        let mut current: Option<&WayPoint> = None;
+    pub fn build_fastest(&mut self, warp: &Warp) {
+        if self.order().len() < 10 {
+            self.find_route_bruteforce_by_time(warp)
+        } else {
+            // Warp time grows with distance, so the closest point is also the fastest one
+            self.find_route_greedy()
+        }
+    }
+
+    fn route_length(&self, route: &[i32]) -> f64 {
+        self.route_cost(route, |a, b| a.distance_to(b))
+    }
+
+    fn route_time(&self, route: &[i32], warp: &Warp) -> f64 {
+        self.route_cost(route, |a, b| warp.time(a.distance_to(b)))
+    }
+
+    /// Cost of flying the whole route: the leg from the departure point to the first waypoint
+    /// is included, as is the final leg to the arrival point when one is set.
+    fn route_cost<F>(&self, route: &[i32], cost: F) -> f64
+    where
+        F: Fn(&WayPoint, &WayPoint) -> f64,
+    {
+        let mut total = 0.0;
+        let mut current = &self.start;
         for id in route {
             let waipoint: &WayPoint = self
                 .waypoints
                 .get(id)
-                .expect(&format!("The {id} must be in map"));
-            if let Some(current) = current {
-                distance += current.distance_to(&waipoint);
-            }
-            current = Some(&waipoint);
+                .unwrap_or_else(|| panic!("The {id} must be in map"));
+            total += cost(current, waipoint);
+            current = waipoint;
         }
-        return distance;
+        if let Some(arrival) = &self.arrival {
+            total += cost(current, arrival);
+        }
+        total
     }
 
     pub fn find_route_bruteforce(&mut self) {
@@ -85,7 +138,7 @@ impl Route {
         let permutations = self.order.iter().permutations(self.order.len());
         let mut best = Vec::new();
         for permutaion in permutations {
-            let mut route = permutaion.into_iter().cloned().collect();
+            let mut route: Vec<i32> = permutaion.into_iter().cloned().collect();
             let length = self.route_length(&route);
             if length < minimal {
                 minimal = length;
@@ -95,6 +148,21 @@ impl Route {
         swap(&mut self.order, &mut best);
     }
 
+    pub fn find_route_bruteforce_by_time(&mut self, warp: &Warp) {
+        let mut minimal = f64::MAX;
+        let permutations = self.order.iter().permutations(self.order.len());
+        let mut best = Vec::new();
+        for permutaion in permutations {
+            let mut route: Vec<i32> = permutaion.into_iter().cloned().collect();
+            let time = self.route_time(&route, warp);
+            if time < minimal {
+                minimal = time;
+                swap(&mut best, &mut route);
+            }
+        }
+        swap(&mut self.order, &mut best);
+    }
+
     pub fn find_route_greedy(&mut self) {
         let mut visited: HashSet<i32> = HashSet::new();
         let mut route: Vec<i32> = Vec::new();
@@ -148,6 +216,44 @@ impl fmt::Display for Route {
         for (_, wp) in &self.waypoints {
             writeln!(f, "{}", wp)?;
         }
+        if let Some(arrival) = &self.arrival {
+            writeln!(f, "{}", arrival)?;
+        }
         write!(f, "")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::Position;
+
+    fn waypoint(id: i32, x: f64) -> WayPoint {
+        WayPoint::new(id, &format!("Point {id}"), &Position::new(x, 0.0, 0.0))
+    }
+
+    #[test]
+    fn route_cost_includes_departure() {
+        let mut route = Route::new(waypoint(0, 0.0));
+        route.add(waypoint(3, 10.0));
+        route.add(waypoint(2, 5.0));
+        route.add(waypoint(1, 1.0));
+        assert_eq!(route.len(), 10.0 + 5.0 + 4.0);
+
+        // Both directions cover the same waypoints, only the leg from the departure differs
+        route.find_route_bruteforce();
+        assert_eq!(route.order(), &vec![1, 2, 3]);
+        assert_eq!(route.len(), 10.0);
+    }
+
+    #[test]
+    fn route_cost_includes_arrival() {
+        let mut route = Route::new(waypoint(0, 0.0));
+        route.add(waypoint(1, 1.0));
+        route.add(waypoint(2, 5.0));
+        route.set_arrival(waypoint(9, 0.0));
+
+        route.find_route_bruteforce();
+        assert_eq!(route.len(), 10.0);
+    }
+}
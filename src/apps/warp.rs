@@ -0,0 +1,153 @@
+use crate::common::units::AU;
+use crate::universe;
+use std::fmt;
+
+use anyhow::anyhow;
+
+const MASS: i32 = 4;
+const MAX_VELOCITY: i32 = 37;
+const AGILITY: i32 = 70;
+const WARP_SPEED_MULTIPLIER: i32 = 600;
+const BASE_WARP_SPEED: i32 = 1281;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Warp {
+    pub warp_speed: f64,
+    pub subwarp_speed: f64,
+    pub align_time: f64,
+}
+impl Warp {
+    pub fn new(warp_speed: f64, subwarp_speed: f64, align_time: f64) -> Self {
+        Self {
+            warp_speed,
+            subwarp_speed,
+            align_time,
+        }
+    }
+
+    pub fn time(&self, distance: f64) -> f64 {
+        // https://wiki.eveuniversity.org/Warp#Warp_time_calculation
+        let k_accel = self.warp_speed;
+        let k_decel = (self.warp_speed / 3.0).min(2.0);
+        let v_warp = self.warp_speed * AU;
+        let v_exit = (self.subwarp_speed / 2.0).min(100.0);
+
+        let d_accel = v_warp / k_accel;
+        let d_decel = v_warp / k_decel;
+        let warp = if distance < d_accel + d_decel {
+            let v_peak = (distance * k_accel * k_decel / (k_accel + k_decel)).max(k_accel);
+            let t_accel = (v_peak / k_accel).ln() / k_accel;
+            let t_decel = (v_peak / v_exit).max(1.0).ln() / k_decel;
+            t_accel + t_decel
+        } else {
+            let t_accel = (v_warp / k_accel).ln() / k_accel;
+            let t_cruise = (distance - d_accel - d_decel) / v_warp;
+            let t_decel = (v_warp / v_exit).ln() / k_decel;
+            t_accel + t_cruise + t_decel
+        };
+        self.align_time + warp
+    }
+}
+
+impl TryFrom<&universe::Type> for Warp {
+    type Error = anyhow::Error;
+
+    fn try_from(ship: &universe::Type) -> anyhow::Result<Self> {
+        let attribute = |id: i32| {
+            ship.attribute(id)
+                .ok_or(anyhow!("The {} has no dogma attribute {id}", ship.name))
+        };
+
+        let base_warp_speed = ship.attribute(BASE_WARP_SPEED).unwrap_or(1.0);
+        let warp_speed = base_warp_speed * attribute(WARP_SPEED_MULTIPLIER)?;
+        let subwarp_speed = attribute(MAX_VELOCITY)?;
+        let mass = ship
+            .mass
+            .or(ship.attribute(MASS))
+            .ok_or(anyhow!("The {} has no mass", ship.name))?;
+        // Entering warp requires 75% of the maximum velocity
+        let align_time = 4.0_f64.ln() * attribute(AGILITY)? as f64 * mass as f64 / 1_000_000.0;
+
+        Ok(Self::new(
+            warp_speed as f64,
+            subwarp_speed as f64,
+            align_time,
+        ))
+    }
+}
+
+impl fmt::Display for Warp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:.2} AU/s, {:.0} m/s, align {:.1} s",
+            self.warp_speed, self.subwarp_speed, self.align_time
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::universe::item::DogmaAttributes;
+    use approx::assert_relative_eq;
+
+    fn attribute(attribute_id: i32, value: f32) -> DogmaAttributes {
+        DogmaAttributes {
+            attribute_id,
+            value,
+        }
+    }
+
+    #[test]
+    fn long_warp() {
+        let warp = Warp::new(3.0, 200.0, 0.0);
+        let k_decel = 1.0;
+        let expected = (AU).ln() / 3.0 + (3.0 * AU).ln() / k_decel - (100.0_f64).ln() / k_decel
+            + (10.0 * AU - AU - 3.0 * AU) / (3.0 * AU);
+
+        assert_relative_eq!(warp.time(10.0 * AU), expected, epsilon = 1e-9);
+    }
+
+    #[test]
+    fn short_warp_is_faster_than_long_one() {
+        let warp = Warp::new(5.0, 300.0, 2.5);
+
+        assert!(warp.time(150_000.0) > warp.align_time);
+        assert!(warp.time(150_000.0) < warp.time(1_000_000_000.0));
+        assert!(warp.time(1.0 * AU) < warp.time(2.0 * AU));
+        assert!(warp.time(10.0 * AU) < warp.time(20.0 * AU));
+    }
+
+    #[test]
+    fn from_type() -> anyhow::Result<()> {
+        let ship = universe::Type {
+            type_id: 587,
+            name: String::from("Rifter"),
+            mass: Some(1_067_000.0),
+            dogma_attributes: Some(vec![
+                attribute(MAX_VELOCITY, 365.0),
+                attribute(AGILITY, 3.2),
+                attribute(WARP_SPEED_MULTIPLIER, 5.0),
+                attribute(BASE_WARP_SPEED, 1.0),
+            ]),
+            ..Default::default()
+        };
+        let warp = Warp::try_from(&ship)?;
+
+        assert_relative_eq!(warp.warp_speed, 5.0);
+        assert_relative_eq!(warp.subwarp_speed, 365.0);
+        assert_relative_eq!(warp.align_time, 4.733, epsilon = 1e-3);
+        Ok(())
+    }
+
+    #[test]
+    fn from_type_without_attributes() {
+        let ship = universe::Type {
+            type_id: 2502,
+            name: String::from("Minmatar Trade Post"),
+            ..Default::default()
+        };
+        assert!(Warp::try_from(&ship).is_err());
+    }
+}
@@ -27,7 +27,22 @@ async fn main() -> anyhow::Result<()> {
         let zkbinfo_save_api = format!("http://{host}:{port}/killmail/save");
         info!("zkbinfo API SAVE url: {zkbinfo_save_api}");
 
-        if let Ok(date) = NaiveDate::parse_from_str(args[1].as_str(), "%Y-%m-%d") {
+        if args.len() == 3 && args[1] == "war" {
+            let id = args[2].parse::<i32>()?;
+            let killmails = api.war_killmails(id).await?;
+            info!("Received {} killmails of the war {id}", killmails.len());
+
+            for killmail in killmails {
+                transfer(
+                    &api,
+                    &client,
+                    killmail.killmail_id,
+                    killmail.killmail_hash,
+                    &zkbinfo_save_api,
+                )
+                .await?;
+            }
+        } else if let Ok(date) = NaiveDate::parse_from_str(args[1].as_str(), "%Y-%m-%d") {
             let zkb_api = format!(
                 "https://zkillboard.com/api/history/{}.json",
                 date.format("%Y%m%d").to_string()
@@ -57,20 +72,7 @@ async fn main() -> anyhow::Result<()> {
             info!("Need to get {} killmails from zkillboard.com", map.len());
 
             for (id, hash) in map {
-                let uid = Uid::Killmail(id, hash.clone());
-                if let Ok(killmail) = api.load::<Killmail>(&uid).await {
-                    post(&client, &killmail, &zkbinfo_save_api).await?;
-                } else {
-                    for i in 1..11 {
-                        let timeout = i * i;
-                        info!("Will retry after {timeout} seconds for {{ {id} {hash} }}");
-                        thread::sleep(Duration::from_secs(timeout));
-                        if let Ok(killmail) = api.load::<Killmail>(&uid).await {
-                            post(&client, &killmail, &zkbinfo_save_api).await?;
-                            break;
-                        }
-                    }
-                }
+                transfer(&api, &client, id, hash, &zkbinfo_save_api).await?;
             }
         }
     } else {
@@ -79,12 +81,36 @@ async fn main() -> anyhow::Result<()> {
     Ok(())
 }
 
-async fn post(client: &reqwest::Client, killmail: &Killmail, api: &String) -> anyhow::Result<()> {
+async fn transfer(
+    api: &EveApi,
+    client: &reqwest::Client,
+    id: i32,
+    hash: String,
+    zkbinfo_save_api: &str,
+) -> anyhow::Result<()> {
+    let uid = Uid::Killmail(id, hash.clone());
+    if let Ok(killmail) = api.load::<Killmail>(&uid).await {
+        post(client, &killmail, zkbinfo_save_api).await?;
+    } else {
+        for i in 1..11 {
+            let timeout = i * i;
+            info!("Will retry after {timeout} seconds for {{ {id} {hash} }}");
+            thread::sleep(Duration::from_secs(timeout));
+            if let Ok(killmail) = api.load::<Killmail>(&uid).await {
+                post(client, &killmail, zkbinfo_save_api).await?;
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn post(client: &reqwest::Client, killmail: &Killmail, api: &str) -> anyhow::Result<()> {
     client.post(api).json(&killmail).send().await?;
     info!("{} -> {}", killmail.killmail_id, killmail.killmail_time);
     Ok(())
 }
 
 fn usage(app: &String) -> () {
-    println!("Usage:\n\t{app} <YYYY-MM-DD>");
+    println!("Usage:\n\t{app} <YYYY-MM-DD>\n\t{app} war <id>");
 }
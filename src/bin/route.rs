@@ -3,32 +3,71 @@ use std::collections::HashMap;
 use docopt::Docopt;
 use serde::Deserialize;
 
+use evetech::apps::circuit::Step;
+use evetech::apps::danger::ON_GRID;
+use evetech::apps::dscan::SpatialIndex;
+use evetech::apps::geometry::{PERCH_MAX, PERCH_MIN};
+use evetech::apps::Circuit;
+use evetech::apps::Danger;
+use evetech::apps::Geometry;
 use evetech::apps::Route;
+use evetech::apps::Warp;
 use evetech::apps::WayPoint;
+use evetech::common::units::{self, AU};
 use evetech::common::Position;
 use evetech::esi::EveApi;
 use evetech::esi::Uid;
+use evetech::models::Api;
 use evetech::universe;
 
 const USAGE: &'static str = "
 Eve Route Builder
 
 Usage:
-  route <system> [--mode=<mode>]
+  route <system> [--mode=<mode>] [--ship=<name>] [--db=<path>] [--hours=<n>]
+  route circuit <systems>... [--ship=<name>] [--db=<path>] [--hours=<n>] [--avoid=<kills>] [--penalty=<jumps>]
+  route circuit --constellation=<name> [--ship=<name>] [--db=<path>] [--hours=<n>] [--avoid=<kills>] [--penalty=<jumps>]
+  route safespot <system> [--clearance=<AU>] [--range=<AU>] [--perch=<km>]
+  route dscan <system> <celestial> [--range=<AU>] [--nearest=<n>]
   route (-h | --help)
   route --version
 
 
 Options:
-  -h --help       Show this screen.
-  --version       Show version.
-  --mode=<mode>   Set route build algorithm  [default: None].
+  -h --help               Show this screen.
+  --version               Show version.
+  --mode=<mode>           Set route build algorithm  [default: None].
+  --ship=<name>           Estimate warp time and build the fastest route for the ship.
+  --constellation=<name>  Visit every system of the constellation.
+  --db=<path>             Annotate the route with recent kills from the zkbinfo database.
+  --hours=<n>             Count kills for the last hours  [default: 24].
+  --avoid=<kills>         Avoid systems with at least the number of recent kills.
+  --penalty=<jumps>       Count each recent kill in a system as extra jumps.
+  --clearance=<AU>        Minimal distance from a safe spot to every celestial  [default: 1.0].
+  --range=<AU>            Directional scan range  [default: 14.3].
+  --perch=<km>            Distance from a stargate to a perch.
+  --nearest=<n>           Number of the nearest celestials to show  [default: 5].
 ";
 
 #[derive(Debug, Deserialize)]
 struct Args {
+    cmd_circuit: bool,
+    cmd_safespot: bool,
+    cmd_dscan: bool,
     arg_system: String,
+    arg_systems: Vec<String>,
+    arg_celestial: String,
     flag_mode: Mode,
+    flag_ship: Option<String>,
+    flag_constellation: Option<String>,
+    flag_db: Option<String>,
+    flag_hours: u16,
+    flag_avoid: Option<usize>,
+    flag_penalty: Option<usize>,
+    flag_clearance: f64,
+    flag_range: f64,
+    flag_perch: Option<f64>,
+    flag_nearest: usize,
 }
 
 #[derive(Debug, Deserialize)]
@@ -44,14 +83,26 @@ async fn main() -> anyhow::Result<()> {
         .and_then(|d| d.deserialize())
         .unwrap_or_else(|e| e.exit());
 
-    build_route(&args.arg_system, &args.flag_mode).await?;
+    if args.cmd_circuit {
+        build_circuit(&args).await?;
+    } else if args.cmd_safespot {
+        find_safespots(&args).await?;
+    } else if args.cmd_dscan {
+        dscan(&args).await?;
+    } else {
+        build_route(&args).await?;
+    }
 
     Ok(())
 }
 
-async fn build_route(system: &String, mode: &Mode) -> anyhow::Result<()> {
+async fn build_route(args: &Args) -> anyhow::Result<()> {
     let api = EveApi::new();
-    let names = vec![system.clone()];
+    let warp = match &args.flag_ship {
+        Some(name) => Some(load_warp(&api, name).await?),
+        None => None,
+    };
+    let names = vec![args.arg_system.clone()];
     let sr = api.search(&names).await?;
     if let Some(systems) = sr.systems {
         for obj in systems {
@@ -59,6 +110,10 @@ async fn build_route(system: &String, mode: &Mode) -> anyhow::Result<()> {
             println!("Solar System: '{}'", obj.name);
             let system = api.load::<universe::System>(&Uid::Id(obj.id)).await?;
             println!("{}", system);
+            let danger = load_danger(&args.flag_db, &[obj.id], args.flag_hours)?;
+            if let Some(danger) = &danger {
+                println!("{}", danger);
+            }
 
             if let Some(id) = system.star_id {
                 let star = api.load::<universe::Star>(&Uid::Id(id)).await?;
@@ -99,21 +154,325 @@ async fn build_route(system: &String, mode: &Mode) -> anyhow::Result<()> {
                 let id = system.system_id;
                 let index = (selected - 1) as usize;
                 let start = &starts[index];
-                best_route(&api, id, start, &mode).await?;
+                best_route(
+                    &api,
+                    id,
+                    start,
+                    &args.flag_mode,
+                    warp.as_ref(),
+                    danger.as_ref(),
+                )
+                .await?;
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn build_circuit(args: &Args) -> anyhow::Result<()> {
+    let api = EveApi::new();
+    let warp = match &args.flag_ship {
+        Some(name) => Some(load_warp(&api, name).await?),
+        None => None,
+    };
+
+    let ids = match &args.flag_constellation {
+        Some(name) => {
+            let sr = api.search(&vec![name.clone()]).await?;
+            let obj = sr
+                .constellations
+                .and_then(|objects| objects.into_iter().next())
+                .ok_or(anyhow::anyhow!("The constellation '{name}' was not found"))?;
+            api.load::<universe::Constellation>(&Uid::Id(obj.id))
+                .await?
+                .systems
+        }
+        None => api
+            .search(&args.arg_systems)
+            .await?
+            .systems
+            .unwrap_or_default()
+            .into_iter()
+            .map(|obj| obj.id)
+            .collect(),
+    };
+
+    let mut names = HashMap::new();
+    let mut circuit = Circuit::new();
+    for id in &ids {
+        let system = api.load::<universe::System>(&Uid::Id(*id)).await?;
+        println!("Solar System: '{}'", system.name);
+        names.insert(system.system_id, system.name.clone());
+
+        for id in system.stargates.unwrap_or_default() {
+            let stargate = api.load::<universe::Stargate>(&Uid::Id(id)).await?;
+            circuit.add_stargate(&stargate);
+        }
+
+        for planet in system.planets.unwrap_or_default() {
+            for id in planet.asteroid_belts.unwrap_or_default() {
+                let belt = api.load::<universe::AsteroidBelt>(&Uid::Id(id)).await?;
+                circuit.add_site(
+                    system.system_id,
+                    WayPoint::new(id, &belt.name, &belt.position),
+                );
+            }
+        }
+    }
+    println!();
+
+    let danger = load_danger(&args.flag_db, &circuit.systems(), args.flag_hours)?;
+    if let Some(danger) = &danger {
+        println!("{}", danger);
+        if let Some(threshold) = args.flag_avoid {
+            for id in danger.hot(threshold) {
+                circuit.avoid(id);
+            }
+        }
+        if let Some(jumps) = args.flag_penalty {
+            for id in circuit.systems() {
+                circuit.penalize(id, jumps * danger.kills(id));
+            }
+        }
+    }
+
+    let itinerary = circuit.build(&ids)?;
+    for id in &itinerary.skipped {
+        let name = names.get(id).cloned().unwrap_or_default();
+        println!("Skipped '{name}': too many recent kills");
+    }
+    let mut idx: u32 = 1u32;
+    for step in &itinerary.steps {
+        match step {
+            Step::Jump {
+                stargate,
+                destination,
+                ..
+            } => {
+                let name = names.get(destination).cloned().unwrap_or_default();
+                match &danger {
+                    Some(danger) => println!(
+                        "   {} => {} [{} kills]",
+                        stargate.name,
+                        name,
+                        danger.kills(*destination)
+                    ),
+                    None => println!("   {} => {}", stargate.name, name),
+                }
+            }
+            Step::Warp { system_id, to, .. } => {
+                let kills = kills_near(danger.as_ref(), *system_id, to);
+                match &warp {
+                    Some(warp) => {
+                        let time = warp.time(step.distance());
+                        println!("{:02} {} ({:.1} s){}", idx, to, time, kills)
+                    }
+                    None => println!("{:02} {}{}", idx, to, kills),
+                }
+                idx += 1;
+            }
+        }
+    }
+    println!();
+    println!("Total jumps {}", itinerary.jumps());
+    println!("Total warp distance {:.1} AU", itinerary.distance() / AU);
+    if let Some(warp) = &warp {
+        println!("Total warp time {:.0} s", itinerary.time(warp));
+    }
+
+    Ok(())
+}
+
+struct Celestials {
+    all: Vec<WayPoint>,
+    stargates: Vec<WayPoint>,
+    stations: Vec<WayPoint>,
+}
+
+async fn load_system(api: &EveApi, name: &str) -> anyhow::Result<universe::System> {
+    let sr = api.search(&vec![name.to_string()]).await?;
+    let obj = sr
+        .systems
+        .and_then(|systems| systems.into_iter().next())
+        .ok_or(anyhow::anyhow!("The system '{name}' was not found"))?;
+    api.load::<universe::System>(&Uid::Id(obj.id)).await
+}
+
+async fn load_celestials(api: &EveApi, system: &universe::System) -> anyhow::Result<Celestials> {
+    let mut celestials = Celestials {
+        all: Vec::new(),
+        stargates: Vec::new(),
+        stations: Vec::new(),
+    };
+
+    if let Some(id) = system.star_id {
+        let star = api.load::<universe::Star>(&Uid::Id(id)).await?;
+        celestials
+            .all
+            .push(WayPoint::new(id, &star.name, &Position::zero()));
+    }
+
+    for planet in system.planets.clone().unwrap_or_default() {
+        let obj = api
+            .load::<universe::Planet>(&Uid::Id(planet.planet_id))
+            .await?;
+        celestials
+            .all
+            .push(WayPoint::new(obj.planet_id, &obj.name, &obj.position));
+        for id in planet.asteroid_belts.unwrap_or_default() {
+            let belt = api.load::<universe::AsteroidBelt>(&Uid::Id(id)).await?;
+            celestials
+                .all
+                .push(WayPoint::new(id, &belt.name, &belt.position));
+        }
+    }
+
+    for id in system.stargates.clone().unwrap_or_default() {
+        let stargate = api.load::<universe::Stargate>(&Uid::Id(id)).await?;
+        let wp = WayPoint::new(id, &stargate.name, &stargate.position);
+        celestials.stargates.push(wp.clone());
+        celestials.all.push(wp);
+    }
+
+    for id in system.stations.clone().unwrap_or_default() {
+        let station = api.load::<universe::Station>(&Uid::Id(id)).await?;
+        let wp = WayPoint::new(id, &station.name, &station.position);
+        celestials.stations.push(wp.clone());
+        celestials.all.push(wp);
+    }
+
+    Ok(celestials)
+}
+
+async fn find_safespots(args: &Args) -> anyhow::Result<()> {
+    let api = EveApi::new();
+    let system = load_system(&api, &args.arg_system).await?;
+    println!("Solar System: '{}'", system.name);
+
+    let celestials = load_celestials(&api, &system).await?;
+    let grids = celestials
+        .stargates
+        .iter()
+        .chain(celestials.stations.iter())
+        .map(|wp| wp.id)
+        .collect::<Vec<i32>>();
+    let geometry = Geometry::new(celestials.all.clone());
+
+    println!();
+    println!("Safe spots:");
+    let clearance = units::from_au(args.flag_clearance);
+    let range = units::from_au(args.flag_range);
+    let spots = geometry.safe_spots(&grids, clearance, range);
+    for (idx, spot) in spots.iter().enumerate() {
+        println!("{:02} {}", idx + 1, spot);
+    }
+
+    let distances = match args.flag_perch {
+        Some(km) => vec![units::from_km(km)],
+        None => vec![PERCH_MIN, PERCH_MAX],
+    };
+    for stargate in &celestials.stargates {
+        println!();
+        println!("Perches at {}:", stargate.name);
+        for distance in &distances {
+            for perch in geometry.perches(stargate, *distance) {
+                println!("   {}", perch);
             }
         }
     }
+
+    Ok(())
+}
+
+async fn dscan(args: &Args) -> anyhow::Result<()> {
+    let api = EveApi::new();
+    let system = load_system(&api, &args.arg_system).await?;
+    println!("Solar System: '{}'", system.name);
+
+    let celestials = load_celestials(&api, &system).await?;
+    let name = args.arg_celestial.to_lowercase();
+    let origin = celestials
+        .all
+        .iter()
+        .find(|wp| wp.name.to_lowercase() == name || wp.id.to_string() == name)
+        .cloned()
+        .ok_or(anyhow::anyhow!(
+            "The celestial '{}' was not found in {}",
+            args.arg_celestial,
+            system.name
+        ))?;
+    let index = SpatialIndex::new(celestials.all);
+
+    println!();
+    println!("D-Scan from {} at {} AU:", origin.name, args.flag_range);
+    let range = units::from_au(args.flag_range);
+    for (wp, distance) in index.range(&origin.position, range) {
+        if wp.id != origin.id {
+            println!("   {} - {}", wp.name, units::format(distance));
+        }
+    }
+
+    println!();
+    println!("Nearest to {}:", origin.name);
+    let nearest = index.nearest(&origin.position, args.flag_nearest + 1);
+    for (wp, distance) in nearest.into_iter().filter(|(wp, _)| wp.id != origin.id) {
+        println!("   {} - {}", wp.name, units::format(distance));
+    }
+
     Ok(())
 }
 
-async fn best_route(api: &EveApi, id: i32, start: &WayPoint, _: &Mode) -> anyhow::Result<()> {
+fn load_danger(db: &Option<String>, systems: &[i32], hours: u16) -> anyhow::Result<Option<Danger>> {
+    match db {
+        Some(uri) => {
+            let api = Api::new(uri, 1)?;
+            let mut danger = Danger::new(hours);
+            for (killmail_id, system_id, position) in api.recent_kills(systems, hours)? {
+                danger.add(system_id, killmail_id, position);
+            }
+            Ok(Some(danger))
+        }
+        None => Ok(None),
+    }
+}
+
+fn kills_near(danger: Option<&Danger>, system_id: i32, waypoint: &WayPoint) -> String {
+    match danger.map(|danger| danger.kills_near(system_id, waypoint, ON_GRID)) {
+        Some(kills) if kills > 0 => format!(" [{kills} kills]"),
+        _ => String::new(),
+    }
+}
+
+async fn load_warp(api: &EveApi, name: &str) -> anyhow::Result<Warp> {
+    let names = vec![name.to_string()];
+    let sr = api.search(&names).await?;
+    let obj = sr
+        .inventory_types
+        .and_then(|types| types.into_iter().next())
+        .ok_or(anyhow::anyhow!("The ship '{name}' was not found"))?;
+    let ship = api.load::<universe::Type>(&Uid::Id(obj.id)).await?;
+    let warp = Warp::try_from(&ship)?;
+    println!("Ship: '{}' {}", ship.name, warp);
+    Ok(warp)
+}
+
+async fn best_route(
+    api: &EveApi,
+    id: i32,
+    start: &WayPoint,
+    _: &Mode,
+    warp: Option<&Warp>,
+    danger: Option<&Danger>,
+) -> anyhow::Result<()> {
     let system = api.load::<universe::System>(&Uid::Id(id)).await?;
     if let Some(planets) = system.planets {
         let mut route = Route::new(start.clone());
         let mut routes = HashMap::new();
         for planet in &planets {
             if let Some(belts) = &planet.asteroid_belts {
-                let planet = api.load::<universe::Planet>(&Uid::Id(planet.planet_id)).await?;
+                let planet = api
+                    .load::<universe::Planet>(&Uid::Id(planet.planet_id))
+                    .await?;
 
                 route.add(WayPoint::new(
                     planet.planet_id,
@@ -132,9 +491,10 @@ async fn best_route(api: &EveApi, id: i32, start: &WayPoint, _: &Mode) -> anyhow
         }
 
         let mut idx: u32 = 1u32;
+        let mut total = 0.0;
 
-        route.build_best();
-        print(&route, &mut idx);
+        build(&mut route, warp);
+        print(&route, &mut idx, warp, danger, id);
 
         idx = 1;
         let mut start = None;
@@ -144,29 +504,49 @@ async fn best_route(api: &EveApi, id: i32, start: &WayPoint, _: &Mode) -> anyhow
                     if let Some(start) = start {
                         route.set_departue(start);
                     }
-                    route.build_best();
-                    print(&route, &mut idx);
+                    build(route, warp);
+                    print(route, &mut idx, warp, danger, system.system_id);
+                    if let Some(warp) = warp {
+                        total += route.time(warp);
+                    }
                 }
                 start = Some(waypoint.clone());
             }
         }
+
+        if warp.is_some() {
+            println!("Total warp time {:.0} s", total);
+        }
     }
 
     Ok(())
 }
 
-fn print(route: &Route, idx: &mut u32) {
-    let mut skip = true;
-    for id in route.complete() {
-        if let Some(wp) = route.get(&id) {
-            if skip {
-                println!("   {}", wp);
-                skip = false;
-            } else {
-                println!("{:02} {}", idx, wp);
-                *idx += 1;
+fn build(route: &mut Route, warp: Option<&Warp>) {
+    match warp {
+        Some(warp) => route.build_fastest(warp),
+        None => route.build_best(),
+    }
+}
+
+fn print(
+    route: &Route,
+    idx: &mut u32,
+    warp: Option<&Warp>,
+    danger: Option<&Danger>,
+    system_id: i32,
+) {
+    println!("   {}", route.start());
+    for (from, to) in route.legs() {
+        let kills = kills_near(danger, system_id, to);
+        match warp {
+            Some(warp) => {
+                let time = warp.time(from.distance_to(to));
+                println!("{:02} {} ({:.1} s){}", idx, to, time, kills);
             }
+            None => println!("{:02} {}{}", idx, to, kills),
         }
+        *idx += 1;
     }
     // println!("Total route length {:.0} Mm", route.len() / 1_000_000.0);
     // println!();
@@ -0,0 +1,87 @@
+use chrono::NaiveDate;
+use docopt::Docopt;
+use serde::Deserialize;
+
+use evetech::models::{Api, Archive, SaveStatus};
+
+const USAGE: &str = "
+Killmail Archive
+
+Usage:
+  zkbarchive export <dir> [--days=<n>] [--db=<uri>]
+  zkbarchive import <dir> [--from=<date>] [--to=<date>] [--skip-expired] [--days=<n>] [--db=<uri>]
+  zkbarchive (-h | --help)
+
+Options:
+  -h --help       Show this screen.
+  --days=<n>      Archive and delete killmails older than the number of days  [default: 90].
+  --from=<date>   Import archived days starting from the date (YYYY-MM-DD).
+  --to=<date>     Import archived days up to the date (YYYY-MM-DD).
+  --skip-expired  Do not import the days older than --days.
+  --db=<uri>      The killmail database  [default: killmails.db].
+
+zkbinfo archives killmails older than ZKBINFO_DAYS on every clean up, so raise it
+to cover the imported days or they are archived and deleted again.
+";
+
+#[derive(Debug, Deserialize)]
+struct Args {
+    cmd_export: bool,
+    cmd_import: bool,
+    arg_dir: String,
+    flag_days: u16,
+    flag_from: Option<String>,
+    flag_to: Option<String>,
+    flag_skip_expired: bool,
+    flag_db: String,
+}
+
+fn main() -> anyhow::Result<()> {
+    let args: Args = Docopt::new(USAGE)
+        .and_then(|d| d.deserialize())
+        .unwrap_or_else(|e| e.exit());
+
+    let api = Api::new(&args.flag_db, 1)?;
+    let archive = Archive::new(&args.arg_dir);
+
+    if args.cmd_export {
+        let count = api.archive(&archive, args.flag_days)?;
+        println!("Archived {count} killmails to {}", args.arg_dir);
+    } else if args.cmd_import {
+        let from = date(&args.flag_from)?;
+        let to = date(&args.flag_to)?;
+        let cutoff =
+            (chrono::Utc::now() - chrono::Duration::days(args.flag_days.into())).date_naive();
+        for date in archive.dates(from, to)? {
+            if args.flag_skip_expired && date < cutoff {
+                println!(
+                    "{date}: skipped, older than the {} days kept in the database",
+                    args.flag_days
+                );
+                continue;
+            }
+            let statuses = api.restore(&archive, &date)?;
+            let count = |expected| {
+                statuses
+                    .iter()
+                    .filter(|(_, status)| *status == expected)
+                    .count()
+            };
+            println!(
+                "{date}: {} killmails, {} inserted, {} updated, {} skipped",
+                statuses.len(),
+                count(SaveStatus::Inserted),
+                count(SaveStatus::Updated),
+                count(SaveStatus::Duplicate)
+            );
+        }
+    }
+    Ok(())
+}
+
+fn date(arg: &Option<String>) -> anyhow::Result<Option<NaiveDate>> {
+    match arg {
+        Some(date) => Ok(Some(NaiveDate::parse_from_str(date, "%Y-%m-%d")?)),
+        None => Ok(None),
+    }
+}
@@ -1,31 +1,74 @@
 use actix_cors::Cors;
+use actix_web::http::{header, StatusCode};
 use actix_web::middleware::Logger;
-use actix_web::{web, App, HttpRequest, HttpResponse, HttpServer, Responder};
+use actix_web::{
+    web, App, Either, HttpRequest, HttpResponse, HttpServer, Responder, ResponseError,
+};
 use anyhow::anyhow;
-use diesel::{Connection, SqliteConnection};
+use chrono::FixedOffset;
 use env_logger;
 use log::{debug, error, info};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
 
+use futures_util::stream;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use std::collections::{HashMap, HashSet};
 use std::env;
-use std::sync::Mutex;
+use std::fmt;
+use std::sync::Arc;
 use std::time::Duration;
 
+use evetech::esi::{EveApi, Uid};
+use evetech::killmails::Killmail;
 use evetech::models::Api;
-use evetech::models::{ObjectType, SubjectType};
+use evetech::models::Archive;
+use evetech::models::Location;
+use evetech::models::{
+    Activity, Area, Clash, Gang, Interval, KillFilter, ObjectType, Order, Page, Paged, SaveStatus,
+    SubjectType, Versus,
+};
+use evetech::universe::{Constellation, System};
+use evetech::war::War;
 
 type Context = web::Data<AppState>;
 
+const NAMES_CHUNK: usize = 1000;
+const WAR_SIDES_HOURS: i64 = 1;
+const RECENT_LIMIT: u32 = 50;
+const RECENT_MAX: u32 = 500;
+const FEED_CAPACITY: usize = 1024;
+const KEEP_ALIVE: Duration = Duration::from_secs(30);
+
 pub struct AppState {
-    pub api: Mutex<Api>,
+    pub api: Api,
+    pub esi: EveApi,
+    pub names_days: u16,
+    pub feed: broadcast::Sender<Arc<Killmail>>,
 }
 impl AppState {
-    pub fn new(api: Api) -> Self {
+    pub fn new(api: Api, names_days: u16) -> Self {
+        let (feed, _) = broadcast::channel(FEED_CAPACITY);
         Self {
-            api: Mutex::new(api),
+            api,
+            esi: EveApi::new(),
+            names_days,
+            feed,
         }
     }
 }
 
+async fn blocking<T, F>(ctx: Context, f: F) -> anyhow::Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce(&Api) -> anyhow::Result<T> + Send + 'static,
+{
+    web::block(move || f(&ctx.api))
+        .await
+        .map_err(|e| anyhow!("{e}"))?
+}
+
 #[actix_web::main]
 async fn main() -> anyhow::Result<()> {
     env_logger::init_from_env(env_logger::Env::new().default_filter_or("info"));
@@ -45,18 +88,38 @@ async fn main() -> anyhow::Result<()> {
     let cleanup_period: u64 = env::<u64>("ZKBINFO_PERIOD", 4);
     info!("The ZKBINFO clean up period: {cleanup_period} hours");
 
-    let conn = SqliteConnection::establish(&uri)?;
-    let mut api = Api::new(conn);
-    cleanup(&mut api, keep_days);
+    let readers: u32 = env::<u32>("ZKBINFO_READERS", 8);
+    info!("The ZKBINFO read-only connections: {readers}");
+
+    let archive = env::var("ZKBINFO_ARCHIVE").ok().map(Archive::new);
+    info!("The ZKBINFO archive: {:?}", archive);
 
-    let context = web::Data::new(AppState::new(api));
+    let names_days: u16 = env::<u16>("ZKBINFO_NAMES_DAYS", 30);
+    info!("The ZKBINFO names refresh: {names_days} days");
+
+    let api = Api::new(&uri, readers)?;
+    cleanup(&api, keep_days, archive.as_ref());
+
+    let context = web::Data::new(AppState::new(api, names_days));
     let ctx = context.clone();
     actix_rt::spawn(async move {
         let mut interval = actix_rt::time::interval(Duration::from_secs(60 * 60 * cleanup_period));
         loop {
             interval.tick().await;
-            if let Ok(mut api) = ctx.api.lock() {
-                cleanup(&mut api, keep_days);
+            let archive = archive.clone();
+            let api = ctx.clone();
+            if let Err(err) =
+                web::block(move || cleanup(&api.api, keep_days, archive.as_ref())).await
+            {
+                error!("Clean up failed: {err}");
+            }
+            match blocking(ctx.clone(), |api| api.stored_ids()).await {
+                Ok(ids) => resolve_names(ctx.clone(), ids).await,
+                Err(err) => error!("Names lookup failed: {err}"),
+            }
+            match blocking(ctx.clone(), |api| api.stored_systems()).await {
+                Ok(ids) => resolve_locations(ctx.clone(), ids).await,
+                Err(err) => error!("Locations lookup failed: {err}"),
             }
         }
     });
@@ -71,10 +134,24 @@ async fn main() -> anyhow::Result<()> {
     let report_systems_route = format!("/{{rtype:{result}}}/{{subject:{allowed}}}/{{id}}/systems");
     let report_lost_ships_route = format!("/lost/ship/{{sid}}/{{subject:{allowed}}}/{{id}}");
     let report_lost_in_system_route = format!("/lost/system/{{sid}}/{{subject:{allowed}}}/{{id}}");
+    let activity_route = format!("/activity/{{subject:{allowed}}}/{{id}}");
+    let hotspots_route = "/hotspots/{area:system|constellation|region}";
+    let versus_route = format!("/versus/{{subject:{allowed}}}/{{id}}/{{other:{allowed}}}/{{oid}}");
+    let gang_route = format!("/gang/{{subject:{allowed}}}/{{id}}");
+    let ship_killers_route = "/killers/ship/{sid}";
+    let weapons_route = format!("/weapons/{{subject:{allowed}}}/{{id}}");
+    let ships_against_route =
+        format!("/flown/{{subject:{allowed}}}/{{id}}/against/{{target:{allowed}}}/{{tid}}");
+    let sides = "aggressor|defender";
+    let war_kills_route = format!("/war/{{id}}/{{side:{sides}}}");
+    let war_pilots_route = format!("/war/{{id}}/{{side:{sides}}}/pilots");
+    let war_timeline_route = format!("/war/{{id}}/{{side:{sides}}}/timeline");
 
     HttpServer::new(move || {
         App::new()
             .app_data(context.clone())
+            .app_data(web::PathConfig::default().error_handler(bad_request))
+            .app_data(web::QueryConfig::default().error_handler(bad_request))
             .wrap(
                 Cors::default()
                     .allow_any_origin()
@@ -89,13 +166,30 @@ async fn main() -> anyhow::Result<()> {
                     .route(&report_ships_route, web::get().to(report_ships))
                     .route(&report_systems_route, web::get().to(report_systems))
                     .route(&report_lost_ships_route, web::get().to(lost_ships))
-                    .route(&report_lost_in_system_route, web::get().to(lost_in_system)),
+                    .route(&report_lost_in_system_route, web::get().to(lost_in_system))
+                    .route(&activity_route, web::get().to(activity))
+                    .route(hotspots_route, web::get().to(hotspots))
+                    .route(&versus_route, web::get().to(versus))
+                    .route(&gang_route, web::get().to(gang))
+                    .route(ship_killers_route, web::get().to(ship_killers))
+                    .route(&weapons_route, web::get().to(weapons))
+                    .route(&ships_against_route, web::get().to(ships_against))
+                    .route(&war_kills_route, web::get().to(war_kills))
+                    .route(&war_pilots_route, web::get().to(war_pilots))
+                    .route(&war_timeline_route, web::get().to(war_timeline)),
             )
             .service(
                 web::scope("/killmail")
+                    .route("/recent", web::get().to(recent))
+                    .route("/stream", web::get().to(subscribe))
+                    .route("/id/{id}", web::get().to(killmail))
                     .route("/{date}", web::get().to(ids_by_date))
-                    .route("/save", web::post().to(save)),
+                    .route("/{id}/zkb", web::get().to(zkb))
+                    .route("/save", web::post().to(save))
+                    .route("/save/batch", web::post().to(save_batch)),
             )
+            .route("/openapi.json", web::get().to(describe))
+            .default_service(web::to(not_found))
             .wrap(Logger::default())
     })
     .workers(6)
@@ -125,206 +219,1480 @@ fn subject<S: Into<String>>(arg: S, id: i32) -> SubjectType {
     }
 }
 
-async fn report_total(ctx: Context, args: web::Path<(String, String, i32)>) -> impl Responder {
+#[derive(Deserialize)]
+pub struct Period {
+    from: Option<i64>,
+    to: Option<i64>,
+    days: Option<u16>,
+}
+
+fn interval(period: web::Query<Period>) -> Interval {
+    let from = period
+        .from
+        .or_else(|| period.days.and_then(|days| Interval::days(days).from));
+    Interval::new(from, period.to)
+}
+
+#[derive(Deserialize)]
+pub struct Paging {
+    limit: Option<u32>,
+    offset: Option<u32>,
+    order: Option<Order>,
+}
+
+fn page(paging: web::Query<Paging>) -> Page {
+    Page::new(
+        paging.limit.map(i64::from),
+        paging.offset.map(i64::from).unwrap_or_default(),
+        paging.order.unwrap_or_default(),
+    )
+}
+
+#[derive(Deserialize)]
+pub struct Kills {
+    system: Option<i32>,
+    ship: Option<i32>,
+    character: Option<i32>,
+    corporation: Option<i32>,
+    alliance: Option<i32>,
+    faction: Option<i32>,
+}
+
+fn kill_filter(kills: web::Query<Kills>) -> KillFilter {
+    let subjects = [
+        kills.character.map(SubjectType::Character),
+        kills.corporation.map(SubjectType::Corporation),
+        kills.alliance.map(SubjectType::Alliance),
+        kills.faction.map(SubjectType::Faction),
+    ];
+    KillFilter {
+        system: kills.system,
+        ship: kills.ship,
+        subjects: subjects.into_iter().flatten().collect(),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct Feed {
+    entities: Option<String>,
+    systems: Option<String>,
+    ships: Option<String>,
+    min_attackers: Option<usize>,
+}
+
+struct Subscription {
+    entities: HashSet<i32>,
+    systems: HashSet<i32>,
+    ships: HashSet<i32>,
+    min_attackers: usize,
+}
+impl Subscription {
+    fn matches(&self, killmail: &Killmail) -> bool {
+        (self.systems.is_empty() || self.systems.contains(&killmail.solar_system_id))
+            && (self.ships.is_empty() || self.ships.contains(&killmail.victim.ship_type_id))
+            && killmail.attackers.len() >= self.min_attackers
+            && (self.entities.is_empty()
+                || killmail
+                    .entities()
+                    .iter()
+                    .any(|id| self.entities.contains(id)))
+    }
+}
+
+fn subscription(feed: web::Query<Feed>) -> std::result::Result<Subscription, Error> {
+    let ids = |list: &Option<String>| {
+        list.iter()
+            .flat_map(|list| list.split(','))
+            .filter(|id| !id.is_empty())
+            .map(|id| {
+                id.trim()
+                    .parse::<i32>()
+                    .map_err(|err| Error::BadRequest(format!("invalid id {id}: {err}")))
+            })
+            .collect::<std::result::Result<HashSet<i32>, Error>>()
+    };
+    Ok(Subscription {
+        entities: ids(&feed.entities)?,
+        systems: ids(&feed.systems)?,
+        ships: ids(&feed.ships)?,
+        min_attackers: feed.min_attackers.unwrap_or_default(),
+    })
+}
+
+#[derive(Deserialize)]
+pub struct Zone {
+    tz: Option<i32>,
+}
+
+fn offset(zone: web::Query<Zone>) -> std::result::Result<FixedOffset, Error> {
+    let minutes = zone.tz.unwrap_or_default();
+    minutes
+        .checked_mul(60)
+        .and_then(FixedOffset::east_opt)
+        .ok_or_else(|| Error::BadRequest(format!("invalid timezone offset: {minutes}")))
+}
+
+#[derive(Deserialize)]
+pub struct Naming {
+    names: Option<bool>,
+}
+
+#[derive(Serialize)]
+pub struct Named<T> {
+    data: T,
+    names: HashMap<i32, String>,
+}
+
+#[derive(Serialize)]
+pub struct Total {
+    count: i64,
+    damage: Option<i64>,
+}
+impl From<(i64, Option<i64>)> for Total {
+    fn from((count, damage): (i64, Option<i64>)) -> Self {
+        Self { count, damage }
+    }
+}
+
+#[derive(Serialize)]
+pub struct Count {
+    id: i32,
+    count: i64,
+}
+impl From<(i32, i64)> for Count {
+    fn from((id, count): (i32, i64)) -> Self {
+        Self { id, count }
+    }
+}
+
+#[derive(Serialize)]
+pub struct Loss {
+    killmail_id: i32,
+    character_id: i32,
+    corporation_id: i32,
+    alliance_id: i32,
+    ship_type_id: i32,
+    damage_taken: i32,
+    solar_system_id: i32,
+    killmail_time: String,
+}
+impl From<(i32, i32, i32, i32, i32, i32, i32, String)> for Loss {
+    fn from(loss: (i32, i32, i32, i32, i32, i32, i32, String)) -> Self {
+        Self {
+            killmail_id: loss.0,
+            character_id: loss.1,
+            corporation_id: loss.2,
+            alliance_id: loss.3,
+            ship_type_id: loss.4,
+            damage_taken: loss.5,
+            solar_system_id: loss.6,
+            killmail_time: loss.7,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct WarKills {
+    kills: i64,
+    isk: Option<f64>,
+}
+impl From<(i64, Option<f64>)> for WarKills {
+    fn from((kills, isk): (i64, Option<f64>)) -> Self {
+        Self { kills, isk }
+    }
+}
+
+#[derive(Serialize)]
+pub struct Day {
+    day: i64,
+    kills: i64,
+}
+impl From<(i64, i64)> for Day {
+    fn from((day, kills): (i64, i64)) -> Self {
+        Self { day, kills }
+    }
+}
+
+#[derive(Serialize)]
+pub struct Side {
+    kills: i64,
+    damage: i64,
+    ships: Vec<Count>,
+}
+impl From<Clash> for Side {
+    fn from(clash: Clash) -> Self {
+        Self {
+            kills: clash.kills,
+            damage: clash.damage,
+            ships: clash.ships.into_iter().map(Count::from).collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct Exchange {
+    day: i64,
+    a: i64,
+    b: i64,
+}
+impl From<(i64, i64, i64)> for Exchange {
+    fn from((day, a, b): (i64, i64, i64)) -> Self {
+        Self { day, a, b }
+    }
+}
+
+#[derive(Serialize)]
+pub struct Matchup {
+    a: Side,
+    b: Side,
+    systems: Vec<Count>,
+    timeline: Vec<Exchange>,
+}
+impl From<Versus> for Matchup {
+    fn from(versus: Versus) -> Self {
+        Self {
+            a: Side::from(versus.a),
+            b: Side::from(versus.b),
+            systems: versus.systems.into_iter().map(Count::from).collect(),
+            timeline: versus.timeline.into_iter().map(Exchange::from).collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct Size {
+    attackers: i64,
+    kills: i64,
+}
+impl From<(i64, i64)> for Size {
+    fn from((attackers, kills): (i64, i64)) -> Self {
+        Self { attackers, kills }
+    }
+}
+
+#[derive(Serialize)]
+pub struct GangSize {
+    kills: i64,
+    solo: f64,
+    median: f64,
+    members: f64,
+    sizes: Vec<Size>,
+}
+impl From<Gang> for GangSize {
+    fn from(gang: Gang) -> Self {
+        Self {
+            kills: gang.kills,
+            solo: gang.solo,
+            median: gang.median,
+            members: gang.members,
+            sizes: gang.sizes.into_iter().map(Size::from).collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct Saved {
+    id: i32,
+    status: SaveStatus,
+}
+impl From<(i32, SaveStatus)> for Saved {
+    fn from((id, status): (i32, SaveStatus)) -> Self {
+        Self { id, status }
+    }
+}
+
+#[derive(Serialize)]
+pub struct Rows<T> {
+    total: i64,
+    rows: Vec<T>,
+}
+
+fn rows<A, B: From<A>>(result: anyhow::Result<Vec<A>>) -> anyhow::Result<Vec<B>> {
+    result.map(|rows| rows.into_iter().map(B::from).collect())
+}
+
+fn paged<A, B: From<A>>(result: anyhow::Result<Paged<A>>) -> anyhow::Result<Rows<B>> {
+    result.map(|paged| Rows {
+        total: paged.total,
+        rows: paged.rows.into_iter().map(B::from).collect(),
+    })
+}
+
+trait Ids {
+    fn ids(&self) -> Vec<i32>;
+}
+impl Ids for Total {
+    fn ids(&self) -> Vec<i32> {
+        Vec::new()
+    }
+}
+impl Ids for Rows<Count> {
+    fn ids(&self) -> Vec<i32> {
+        self.rows.iter().map(|count| count.id).collect()
+    }
+}
+impl Ids for Rows<Loss> {
+    fn ids(&self) -> Vec<i32> {
+        self.rows
+            .iter()
+            .flat_map(|loss| {
+                [
+                    loss.character_id,
+                    loss.corporation_id,
+                    loss.alliance_id,
+                    loss.ship_type_id,
+                    loss.solar_system_id,
+                ]
+            })
+            .collect()
+    }
+}
+impl Ids for Killmail {
+    fn ids(&self) -> Vec<i32> {
+        Killmail::ids(self)
+    }
+}
+impl Ids for Rows<Killmail> {
+    fn ids(&self) -> Vec<i32> {
+        self.rows.iter().flat_map(Killmail::ids).collect()
+    }
+}
+impl Ids for WarKills {
+    fn ids(&self) -> Vec<i32> {
+        Vec::new()
+    }
+}
+impl Ids for Activity {
+    fn ids(&self) -> Vec<i32> {
+        Vec::new()
+    }
+}
+impl Ids for Matchup {
+    fn ids(&self) -> Vec<i32> {
+        self.a
+            .ships
+            .iter()
+            .chain(&self.b.ships)
+            .chain(&self.systems)
+            .map(|count| count.id)
+            .collect()
+    }
+}
+impl Ids for GangSize {
+    fn ids(&self) -> Vec<i32> {
+        Vec::new()
+    }
+}
+impl Ids for Vec<Day> {
+    fn ids(&self) -> Vec<i32> {
+        Vec::new()
+    }
+}
+
+async fn respond<T>(
+    ctx: Context,
+    result: anyhow::Result<T>,
+    naming: web::Query<Naming>,
+) -> Either<Result<T>, Result<Named<T>>>
+where
+    T: Ids + Serialize + Send + 'static,
+{
+    if !naming.names.unwrap_or_default() {
+        return Either::Left(Result::from(result));
+    }
+    let result = match result {
+        Ok(data) => {
+            blocking(ctx, move |api| {
+                Ok(Named {
+                    names: api.names(&data.ids())?,
+                    data,
+                })
+            })
+            .await
+        }
+        Err(err) => Err(err),
+    };
+    Either::Right(Result::from(result))
+}
+
+async fn resolve_names(ctx: Context, ids: Vec<i32>) {
+    let max_age = chrono::Duration::days(ctx.names_days.into());
+    let unnamed = match blocking(ctx.clone(), move |api| api.unnamed(&ids, max_age)).await {
+        Ok(unnamed) => unnamed,
+        Err(err) => return error!("Names lookup failed: {err}"),
+    };
+
+    let mut chunks = unnamed
+        .chunks(NAMES_CHUNK)
+        .map(<[i32]>::to_vec)
+        .collect::<Vec<Vec<i32>>>();
+    let mut names = Vec::new();
+    while let Some(chunk) = chunks.pop() {
+        match ctx.esi.names(&chunk).await {
+            Ok(resolved) => names.extend(resolved),
+            Err(err) if err.is::<reqwest::Error>() => {
+                error!("Names lookup failed: {err}");
+                break;
+            }
+            Err(_) if chunk.len() > 1 => {
+                let (left, right) = chunk.split_at(chunk.len() / 2);
+                chunks.push(left.to_vec());
+                chunks.push(right.to_vec());
+            }
+            Err(err) => debug!("Unresolved name {chunk:?}: {err}"),
+        }
+    }
+    if names.is_empty() {
+        return;
+    }
+
+    match blocking(ctx, move |api| api.save_names(&names)).await {
+        Ok(count) => info!("Resolved {count} names"),
+        Err(err) => error!("Names update failed: {err}"),
+    }
+}
+
+async fn resolve_locations(ctx: Context, ids: Vec<i32>) {
+    let unlocated = match blocking(ctx.clone(), move |api| api.unlocated(&ids)).await {
+        Ok(unlocated) => unlocated,
+        Err(err) => return error!("Locations lookup failed: {err}"),
+    };
+
+    let mut regions = HashMap::new();
+    let mut locations = Vec::new();
+    for id in unlocated {
+        let system = match ctx.esi.load::<System>(&Uid::Id(id)).await {
+            Ok(system) => system,
+            Err(err) if err.is::<reqwest::Error>() => {
+                error!("Locations lookup failed: {err}");
+                break;
+            }
+            Err(err) => {
+                debug!("Unresolved system {id}: {err}");
+                continue;
+            }
+        };
+        let constellation_id = system.constellation_id;
+        let region_id = match regions.get(&constellation_id) {
+            Some(region_id) => *region_id,
+            None => match ctx
+                .esi
+                .load::<Constellation>(&Uid::Id(constellation_id))
+                .await
+            {
+                Ok(constellation) => *regions
+                    .entry(constellation_id)
+                    .or_insert(constellation.region_id),
+                Err(err) if err.is::<reqwest::Error>() => {
+                    error!("Locations lookup failed: {err}");
+                    break;
+                }
+                Err(err) => {
+                    debug!("Unresolved constellation {constellation_id}: {err}");
+                    continue;
+                }
+            },
+        };
+        locations.push(Location {
+            system_id: id,
+            constellation_id,
+            region_id,
+        });
+    }
+    if locations.is_empty() {
+        return;
+    }
+
+    match blocking(ctx, move |api| api.save_locations(&locations)).await {
+        Ok(count) => info!("Resolved {count} locations"),
+        Err(err) => error!("Locations update failed: {err}"),
+    }
+}
+
+async fn report_total(
+    ctx: Context,
+    args: web::Path<(String, String, i32)>,
+    period: web::Query<Period>,
+    naming: web::Query<Naming>,
+) -> impl Responder {
     let (rtype, subj, id) = args.into_inner();
+    let interval = interval(period);
 
-    let result = ctx
-        .api
-        .lock()
-        .map_err(|e| anyhow!("{e}"))
-        .and_then(|mut api| match rtype.as_str() {
-            "wins" => api.wins(subject(subj, id)),
-            "losses" => api.losses(subject(subj, id)),
-            _ => unreachable!(),
-        })
-        .map_err(|e| anyhow!("{e}"));
+    let result = blocking(ctx.clone(), move |api| match rtype.as_str() {
+        "wins" => api.wins(subject(subj, id), interval),
+        "losses" => api.losses(subject(subj, id), interval),
+        _ => unreachable!(),
+    })
+    .await;
 
-    Result::from(result)
+    respond(ctx, result.map(Total::from), naming).await
 }
 
-async fn report_systems(ctx: Context, args: web::Path<(String, String, i32)>) -> impl Responder {
+async fn report_systems(
+    ctx: Context,
+    args: web::Path<(String, String, i32)>,
+    period: web::Query<Period>,
+    paging: web::Query<Paging>,
+    naming: web::Query<Naming>,
+) -> impl Responder {
     let (rtype, subj, id) = args.into_inner();
-    let result = ctx
-        .api
-        .lock()
-        .map_err(|e| anyhow!("{e}"))
-        .and_then(|mut api| match rtype.as_str() {
-            "wins" => api.wins_systems(subject(subj, id)),
-            "losses" => api.losses_systems(subject(subj, id)),
-            _ => unreachable!(),
-        })
-        .map_err(|e| anyhow!("{e}"));
+    let interval = interval(period);
+    let page = page(paging);
+    let result = blocking(ctx.clone(), move |api| match rtype.as_str() {
+        "wins" => api.wins_systems(subject(subj, id), interval, page),
+        "losses" => api.losses_systems(subject(subj, id), interval, page),
+        _ => unreachable!(),
+    })
+    .await;
 
-    Result::from(result)
+    respond(ctx, paged::<_, Count>(result), naming).await
 }
 
-async fn report_ships(ctx: Context, args: web::Path<(String, String, i32)>) -> impl Responder {
+async fn report_ships(
+    ctx: Context,
+    args: web::Path<(String, String, i32)>,
+    period: web::Query<Period>,
+    paging: web::Query<Paging>,
+    naming: web::Query<Naming>,
+) -> impl Responder {
     let (rtype, subj, id) = args.into_inner();
-    let result = ctx
-        .api
-        .lock()
-        .map_err(|e| anyhow!("{e}"))
-        .and_then(|mut api| match rtype.as_str() {
-            "wins" => api.wins_ships(subject(subj, id)),
-            "losses" => api.losses_ships(subject(subj, id)),
-            _ => unreachable!(),
-        })
-        .map_err(|e| anyhow!("{e}"));
+    let interval = interval(period);
+    let page = page(paging);
+    let result = blocking(ctx.clone(), move |api| match rtype.as_str() {
+        "wins" => api.wins_ships(subject(subj, id), interval, page),
+        "losses" => api.losses_ships(subject(subj, id), interval, page),
+        _ => unreachable!(),
+    })
+    .await;
 
-    Result::from(result)
+    respond(ctx, paged::<_, Count>(result), naming).await
 }
 
-async fn friends(ctx: Context, args: web::Path<(String, String, i32)>) -> impl Responder {
+async fn friends(
+    ctx: Context,
+    args: web::Path<(String, String, i32)>,
+    period: web::Query<Period>,
+    paging: web::Query<Paging>,
+    naming: web::Query<Naming>,
+) -> impl Responder {
     let (obj, subj, id) = args.into_inner();
-    let result = ctx
-        .api
-        .lock()
-        .map_err(|e| anyhow!("{e}"))
-        .and_then(|mut api| api.friends(subject(subj, id), object(obj)))
-        .map_err(|e| anyhow!("{e}"));
+    let interval = interval(period);
+    let page = page(paging);
+    let result = blocking(ctx.clone(), move |api| {
+        api.friends(subject(subj, id), object(obj), interval, page)
+    })
+    .await;
 
-    Result::from(result)
+    respond(ctx, paged::<_, Count>(result), naming).await
 }
 
-async fn enemies(ctx: Context, args: web::Path<(String, String, i32)>) -> impl Responder {
+async fn enemies(
+    ctx: Context,
+    args: web::Path<(String, String, i32)>,
+    period: web::Query<Period>,
+    paging: web::Query<Paging>,
+    naming: web::Query<Naming>,
+) -> impl Responder {
     let (obj, subj, id) = args.into_inner();
-    let result = ctx
-        .api
-        .lock()
-        .map_err(|e| anyhow!("{e}"))
-        .and_then(|mut api| api.enemies(subject(subj, id), object(obj)))
-        .map_err(|e| anyhow!("{e}"));
+    let interval = interval(period);
+    let page = page(paging);
+    let result = blocking(ctx.clone(), move |api| {
+        api.enemies(subject(subj, id), object(obj), interval, page)
+    })
+    .await;
 
-    Result::from(result)
+    respond(ctx, paged::<_, Count>(result), naming).await
 }
 
 async fn ids_by_date(ctx: Context, args: web::Path<String>) -> impl Responder {
     let date = args.into_inner();
 
-    let result = ctx
-        .api
-        .lock()
-        .map_err(|e| anyhow!("{e}"))
-        .and_then(|mut api| api.ids_by_date(date))
-        .map_err(|e| anyhow!("{e}"));
+    let result = blocking(ctx, move |api| api.ids_by_date(date)).await;
 
     Result::from(result)
 }
 
-async fn lost_ships(ctx: Context, args: web::Path<(i32, String, i32)>) -> impl Responder {
-    let (sid, subj, id) = args.into_inner();
-    let result = ctx
-        .api
-        .lock()
-        .map_err(|e| anyhow!("{e}"))
-        .and_then(|mut api| api.lost_ships(subject(subj, id), sid))
-        .map_err(|e| anyhow!("{e}"));
+async fn killmail(
+    ctx: Context,
+    args: web::Path<i32>,
+    naming: web::Query<Naming>,
+) -> impl Responder {
+    let id = args.into_inner();
+
+    let result = blocking(ctx.clone(), move |api| api.load(id)).await;
+
+    respond(ctx, result, naming).await
+}
+
+async fn recent(
+    ctx: Context,
+    kills: web::Query<Kills>,
+    period: web::Query<Period>,
+    paging: web::Query<Paging>,
+    naming: web::Query<Naming>,
+) -> impl Responder {
+    let filter = kill_filter(kills);
+    let interval = interval(period);
+    let limit = paging.limit.unwrap_or(RECENT_LIMIT).min(RECENT_MAX);
+    let page = Page {
+        limit: Some(limit.into()),
+        ..page(paging)
+    };
+    let result = blocking(ctx.clone(), move |api| api.kills(&filter, interval, page)).await;
+
+    respond(ctx, paged::<_, Killmail>(result), naming).await
+}
+
+async fn zkb(ctx: Context, args: web::Path<i32>) -> impl Responder {
+    let id = args.into_inner();
+
+    let result = blocking(ctx, move |api| api.zkb(id)).await;
 
     Result::from(result)
 }
 
-async fn lost_in_system(ctx: Context, args: web::Path<(i32, String, i32)>) -> impl Responder {
+async fn lost_ships(
+    ctx: Context,
+    args: web::Path<(i32, String, i32)>,
+    period: web::Query<Period>,
+    paging: web::Query<Paging>,
+    naming: web::Query<Naming>,
+) -> impl Responder {
     let (sid, subj, id) = args.into_inner();
-    let result = ctx
-        .api
-        .lock()
-        .map_err(|e| anyhow!("{e}"))
-        .and_then(|mut api| api.lost_in_system(subject(subj, id), sid))
-        .map_err(|e| anyhow!("{e}"));
+    let interval = interval(period);
+    let page = page(paging);
+    let result = blocking(ctx.clone(), move |api| {
+        api.lost_ships(subject(subj, id), sid, interval, page)
+    })
+    .await;
 
-    Result::from(result)
+    respond(ctx, paged::<_, Loss>(result), naming).await
 }
 
+async fn lost_in_system(
+    ctx: Context,
+    args: web::Path<(i32, String, i32)>,
+    period: web::Query<Period>,
+    paging: web::Query<Paging>,
+    naming: web::Query<Naming>,
+) -> impl Responder {
+    let (sid, subj, id) = args.into_inner();
+    let interval = interval(period);
+    let page = page(paging);
+    let result = blocking(ctx.clone(), move |api| {
+        api.lost_in_system(subject(subj, id), sid, interval, page)
+    })
+    .await;
 
-async fn save(ctx: Context, json: String) -> impl Responder {
-    let result = serde_json::from_str::<evetech::killmails::Killmail>(&json)
-        .map_err(|e| anyhow!("{e}"))
-        .and_then(|killmail| {
-            ctx.api
-                .lock()
-                .map_err(|e| anyhow!("{e}"))
-                .and_then(|mut api| api.save(&killmail))
-        });
-    Result::from(result)
+    respond(ctx, paged::<_, Loss>(result), naming).await
 }
 
-pub struct Result {
-    json: String,
+async fn ship_killers(
+    ctx: Context,
+    args: web::Path<i32>,
+    period: web::Query<Period>,
+    paging: web::Query<Paging>,
+    naming: web::Query<Naming>,
+) -> impl Responder {
+    let sid = args.into_inner();
+    let interval = interval(period);
+    let page = page(paging);
+    let result = blocking(ctx.clone(), move |api| {
+        api.ship_killers(sid, interval, page)
+    })
+    .await;
+
+    respond(ctx, paged::<_, Count>(result), naming).await
 }
-impl From<String> for Result {
-    fn from(json: String) -> Self {
-        debug!("{json}");
-        Self { json }
-    }
+
+async fn weapons(
+    ctx: Context,
+    args: web::Path<(String, i32)>,
+    period: web::Query<Period>,
+    paging: web::Query<Paging>,
+    naming: web::Query<Naming>,
+) -> impl Responder {
+    let (subj, id) = args.into_inner();
+    let interval = interval(period);
+    let page = page(paging);
+    let result = blocking(ctx.clone(), move |api| {
+        api.weapons(subject(subj, id), interval, page)
+    })
+    .await;
+
+    respond(ctx, paged::<_, Count>(result), naming).await
 }
-impl From<anyhow::Error> for Result {
-    fn from(err: anyhow::Error) -> Self {
-        let json = format!(r#"{{ "error": "{err}" }}"#);
-        error!("{json}");
-        Self { json }
+
+async fn ships_against(
+    ctx: Context,
+    args: web::Path<(String, i32, String, i32)>,
+    period: web::Query<Period>,
+    paging: web::Query<Paging>,
+    naming: web::Query<Naming>,
+) -> impl Responder {
+    let (subj, id, target, tid) = args.into_inner();
+    let interval = interval(period);
+    let page = page(paging);
+    let result = blocking(ctx.clone(), move |api| {
+        api.ships_against(subject(subj, id), subject(target, tid), interval, page)
+    })
+    .await;
+
+    respond(ctx, paged::<_, Count>(result), naming).await
+}
+
+async fn activity(
+    ctx: Context,
+    args: web::Path<(String, i32)>,
+    period: web::Query<Period>,
+    zone: web::Query<Zone>,
+    naming: web::Query<Naming>,
+) -> impl Responder {
+    let (subj, id) = args.into_inner();
+    let interval = interval(period);
+    let result = match offset(zone) {
+        Ok(tz) => {
+            blocking(ctx.clone(), move |api| {
+                api.activity(subject(subj, id), interval, tz)
+            })
+            .await
+        }
+        Err(err) => return Either::Left(Result::from(err)),
+    };
+
+    respond(ctx, result, naming).await
+}
+
+async fn gang(
+    ctx: Context,
+    args: web::Path<(String, i32)>,
+    period: web::Query<Period>,
+    naming: web::Query<Naming>,
+) -> impl Responder {
+    let (subj, id) = args.into_inner();
+    let interval = interval(period);
+    let result = blocking(ctx.clone(), move |api| {
+        api.gang(subject(subj, id), interval)
+    })
+    .await;
+
+    respond(ctx, result.map(GangSize::from), naming).await
+}
+
+async fn versus(
+    ctx: Context,
+    args: web::Path<(String, i32, String, i32)>,
+    period: web::Query<Period>,
+    naming: web::Query<Naming>,
+) -> impl Responder {
+    let (subj, id, other, oid) = args.into_inner();
+    let interval = interval(period);
+    let result = blocking(ctx.clone(), move |api| {
+        api.versus(subject(subj, id), subject(other, oid), interval)
+    })
+    .await;
+
+    respond(ctx, result.map(Matchup::from), naming).await
+}
+
+async fn hotspots(
+    ctx: Context,
+    args: web::Path<String>,
+    period: web::Query<Period>,
+    paging: web::Query<Paging>,
+    naming: web::Query<Naming>,
+) -> impl Responder {
+    let area = match args.into_inner().as_str() {
+        "system" => Area::System,
+        "constellation" => Area::Constellation,
+        "region" => Area::Region,
+        _ => unreachable!(),
+    };
+    let interval = interval(period);
+    let page = page(paging);
+    let result = blocking(ctx.clone(), move |api| api.hotspots(area, interval, page)).await;
+
+    respond(ctx, paged::<_, Count>(result), naming).await
+}
+
+async fn losers(ctx: &Context, id: i32, side: &str) -> anyhow::Result<Vec<i32>> {
+    let max_age = chrono::Duration::hours(WAR_SIDES_HOURS);
+    let mut sides = blocking(ctx.clone(), move |api| api.war_sides(id, max_age)).await?;
+    if sides.is_empty() {
+        let war = ctx.esi.load::<War>(&Uid::Id(id)).await?;
+        sides = blocking(ctx.clone(), move |api| {
+            api.save_war_sides(&war)?;
+            api.war_sides(id, max_age)
+        })
+        .await?;
     }
+
+    // the losers of one side are the members of the other one
+    let aggressor = match side {
+        "aggressor" => false,
+        "defender" => true,
+        _ => unreachable!(),
+    };
+    Ok(sides
+        .into_iter()
+        .filter(|(_, side)| *side == aggressor)
+        .map(|(id, _)| id)
+        .collect())
 }
-impl From<anyhow::Result<Vec<i32>>> for Result {
-    fn from(result: anyhow::Result<Vec<i32>>) -> Self {
-        match result {
-            Ok(ids) => match serde_json::to_string(&ids) {
-                Ok(json) => Self::from(json),
-                Err(err) => Self::from(anyhow!("{err}")),
-            },
-            Err(err) => Self::from(err),
+
+async fn war_kills(
+    ctx: Context,
+    args: web::Path<(i32, String)>,
+    period: web::Query<Period>,
+    naming: web::Query<Naming>,
+) -> impl Responder {
+    let (id, side) = args.into_inner();
+    let interval = interval(period);
+    let result = match losers(&ctx, id, &side).await {
+        Ok(losers) => blocking(ctx.clone(), move |api| api.war_kills(id, &losers, interval)).await,
+        Err(err) => Err(err),
+    };
+
+    respond(ctx, result.map(WarKills::from), naming).await
+}
+
+async fn war_pilots(
+    ctx: Context,
+    args: web::Path<(i32, String)>,
+    period: web::Query<Period>,
+    paging: web::Query<Paging>,
+    naming: web::Query<Naming>,
+) -> impl Responder {
+    let (id, side) = args.into_inner();
+    let interval = interval(period);
+    let page = page(paging);
+    let result = match losers(&ctx, id, &side).await {
+        Ok(losers) => {
+            blocking(ctx.clone(), move |api| {
+                api.war_pilots(id, &losers, interval, page)
+            })
+            .await
+        }
+        Err(err) => Err(err),
+    };
+
+    respond(ctx, paged::<_, Count>(result), naming).await
+}
+
+async fn war_timeline(
+    ctx: Context,
+    args: web::Path<(i32, String)>,
+    period: web::Query<Period>,
+    naming: web::Query<Naming>,
+) -> impl Responder {
+    let (id, side) = args.into_inner();
+    let interval = interval(period);
+    let result = match losers(&ctx, id, &side).await {
+        Ok(losers) => {
+            blocking(ctx.clone(), move |api| {
+                api.war_timeline(id, &losers, interval)
+            })
+            .await
         }
+        Err(err) => Err(err),
+    };
+
+    respond(ctx, rows::<_, Day>(result), naming).await
+}
+
+async fn save(ctx: Context, json: String) -> impl Responder {
+    let killmail = match serde_json::from_str::<Killmail>(&json) {
+        Ok(killmail) => killmail,
+        Err(err) => return Result::from(Error::BadRequest(format!("{err}"))),
+    };
+    if let Err(err) = killmail.timestamp() {
+        return Result::from(Error::BadRequest(format!("{err}")));
     }
+
+    let ids = killmail.ids();
+    let systems = vec![killmail.solar_system_id];
+    let result = blocking(ctx.clone(), move |api| {
+        api.save(&killmail).map(|status| (killmail, status))
+    })
+    .await;
+    let result = result.map(|(killmail, status)| {
+        let id = killmail.killmail_id;
+        if status == SaveStatus::Inserted {
+            publish(&ctx, killmail);
+        }
+        (id, status)
+    });
+    if result.is_ok() {
+        actix_rt::spawn(resolve_names(ctx.clone(), ids));
+        actix_rt::spawn(resolve_locations(ctx, systems));
+    }
+
+    Result::from(result.map(Saved::from))
 }
-impl From<anyhow::Result<Vec<(i32, i64)>>> for Result {
-    fn from(result: anyhow::Result<Vec<(i32, i64)>>) -> Self {
-        match result {
-            Ok(ids) => match serde_json::to_string(&ids) {
-                Ok(json) => Self::from(json),
-                Err(err) => Self::from(anyhow!("{err}")),
-            },
-            Err(err) => Self::from(err),
+
+async fn save_batch(ctx: Context, json: String) -> impl Responder {
+    let killmails = match serde_json::from_str::<Vec<Killmail>>(&json) {
+        Ok(killmails) => killmails,
+        Err(err) => return Result::from(Error::BadRequest(format!("{err}"))),
+    };
+    if let Some(Err(err)) = killmails
+        .iter()
+        .map(|killmail| killmail.timestamp())
+        .find(|timestamp| timestamp.is_err())
+    {
+        return Result::from(Error::BadRequest(format!("{err}")));
+    }
+
+    let mut ids = killmails
+        .iter()
+        .flat_map(|killmail| killmail.ids())
+        .collect::<Vec<i32>>();
+    ids.sort();
+    ids.dedup();
+    let mut systems = killmails
+        .iter()
+        .map(|killmail| killmail.solar_system_id)
+        .collect::<Vec<i32>>();
+    systems.sort();
+    systems.dedup();
+    let result = blocking(ctx.clone(), move |api| {
+        api.save_batch(&killmails).map(|saved| (killmails, saved))
+    })
+    .await;
+    let result = result.map(|(killmails, saved)| {
+        let inserted = saved
+            .iter()
+            .filter(|(_, status)| *status == SaveStatus::Inserted)
+            .map(|(id, _)| *id)
+            .collect::<HashSet<i32>>();
+        for killmail in killmails {
+            if inserted.contains(&killmail.killmail_id) {
+                publish(&ctx, killmail);
+            }
         }
+        saved
+    });
+    if result.is_ok() {
+        actix_rt::spawn(resolve_names(ctx.clone(), ids));
+        actix_rt::spawn(resolve_locations(ctx, systems));
     }
+
+    Result::from(rows::<_, Saved>(result))
 }
-impl From<anyhow::Result<(i64, Option<i64>)>> for Result {
-    fn from(result: anyhow::Result<(i64, Option<i64>)>) -> Self {
-        match result {
-            Ok(ids) => match serde_json::to_string(&ids) {
-                Ok(json) => Self::from(json),
-                Err(err) => Self::from(anyhow!("{err}")),
-            },
-            Err(err) => Self::from(err),
+
+fn publish(ctx: &Context, killmail: Killmail) {
+    // sending only fails when nobody is subscribed
+    let _ = ctx.feed.send(Arc::new(killmail));
+}
+
+async fn subscribe(ctx: Context, feed: web::Query<Feed>) -> impl Responder {
+    let subscription = match subscription(feed) {
+        Ok(subscription) => subscription,
+        Err(err) => return Either::Left(Result::<()>::from(err)),
+    };
+    let events = stream::unfold(
+        (ctx.feed.subscribe(), subscription),
+        |(mut feed, subscription)| async move {
+            loop {
+                let event = match actix_rt::time::timeout(KEEP_ALIVE, feed.recv()).await {
+                    Err(_) => String::from(": keep-alive\n\n"),
+                    Ok(Ok(killmail)) if subscription.matches(&killmail) => {
+                        match serde_json::to_string(&*killmail) {
+                            Ok(json) => format!("event: killmail\ndata: {json}\n\n"),
+                            Err(err) => {
+                                error!("Killmail {} encoding failed: {err}", killmail.killmail_id);
+                                continue;
+                            }
+                        }
+                    }
+                    Ok(Ok(_)) => continue,
+                    Ok(Err(RecvError::Lagged(skipped))) => {
+                        format!("event: lagged\ndata: {{\"skipped\":{skipped}}}\n\n")
+                    }
+                    Ok(Err(RecvError::Closed)) => return None,
+                };
+                return Some((
+                    Ok::<_, actix_web::Error>(web::Bytes::from(event)),
+                    (feed, subscription),
+                ));
+            }
+        },
+    );
+
+    Either::Right(
+        HttpResponse::Ok()
+            .content_type("text/event-stream")
+            .insert_header((header::CACHE_CONTROL, "no-cache"))
+            .streaming(events),
+    )
+}
+
+async fn not_found(req: HttpRequest) -> impl Responder {
+    Result::<()>::from(Error::NotFound(format!("No route for {}", req.path())))
+}
+
+async fn describe() -> impl Responder {
+    HttpResponse::Ok().json(openapi())
+}
+
+#[derive(Debug)]
+pub enum Error {
+    BadRequest(String),
+    NotFound(String),
+    Internal(String),
+}
+impl From<anyhow::Error> for Error {
+    fn from(err: anyhow::Error) -> Self {
+        // the ESI client reports unknown objects with the response status
+        let not_found = matches!(
+            err.downcast_ref::<diesel::result::Error>(),
+            Some(diesel::result::Error::NotFound)
+        ) || err.downcast_ref::<reqwest::StatusCode>()
+            == Some(&reqwest::StatusCode::NOT_FOUND);
+        if not_found {
+            Self::NotFound(format!("{err}"))
+        } else {
+            Self::Internal(format!("{err}"))
         }
     }
 }
-impl From<anyhow::Result<Vec<(i32, i32, i32, i32, i32, i32, i32, String)>>> for Result {
-    fn from(result: anyhow::Result<Vec<(i32, i32, i32, i32, i32, i32, i32, String)>>) -> Self {
-        match result {
-            Ok(ids) => match serde_json::to_string(&ids) {
-                Ok(json) => Self::from(json),
-                Err(err) => Self::from(anyhow!("{err}")),
-            },
-            Err(err) => Self::from(err),
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::BadRequest(message) | Self::NotFound(message) | Self::Internal(message) => {
+                write!(f, "{message}")
+            }
         }
     }
 }
-impl From<anyhow::Result<i32>> for Result {
-    fn from(result: anyhow::Result<i32>) -> Self {
-        match result {
-            Ok(id) => Self::from(format!(r#"{{ "id": "{id}" }}"#)),
-            Err(err) => Self::from(err),
+
+#[derive(Serialize)]
+struct ErrorBody {
+    status: u16,
+    error: String,
+}
+impl ResponseError for Error {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::BadRequest(_) => StatusCode::BAD_REQUEST,
+            Self::NotFound(_) => StatusCode::NOT_FOUND,
+            Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
+
+    fn error_response(&self) -> HttpResponse {
+        let status = self.status_code();
+        if status.is_server_error() {
+            error!("{status}: {self}");
+        } else {
+            debug!("{status}: {self}");
+        }
+        HttpResponse::build(status).json(ErrorBody {
+            status: status.as_u16(),
+            error: format!("{self}"),
+        })
+    }
 }
-impl Responder for Result {
+
+fn bad_request<E: fmt::Display>(err: E, _req: &HttpRequest) -> actix_web::Error {
+    Error::BadRequest(format!("{err}")).into()
+}
+
+pub struct Result<T>(std::result::Result<T, Error>);
+impl<T> From<anyhow::Result<T>> for Result<T> {
+    fn from(result: anyhow::Result<T>) -> Self {
+        Self(result.map_err(Error::from))
+    }
+}
+impl<T> From<Error> for Result<T> {
+    fn from(err: Error) -> Self {
+        Self(Err(err))
+    }
+}
+impl<T: Serialize> Responder for Result<T> {
     type Body = actix_web::body::BoxBody;
     fn respond_to(self, _req: &HttpRequest) -> HttpResponse<Self::Body> {
-        HttpResponse::Ok()
-            .content_type(actix_web::http::header::ContentType::json())
-            .body(self.json)
+        let json = self.0.and_then(|data| {
+            serde_json::to_string(&data).map_err(|e| Error::Internal(format!("{e}")))
+        });
+        match json {
+            Ok(json) => {
+                debug!("{json}");
+                HttpResponse::Ok()
+                    .content_type(actix_web::http::header::ContentType::json())
+                    .body(json)
+            }
+            Err(err) => err.error_response(),
+        }
     }
 }
 
+fn openapi() -> serde_json::Value {
+    let path = |name: &str, schema: serde_json::Value| {
+        json!({
+            "name": name,
+            "in": "path",
+            "required": true,
+            "schema": schema
+        })
+    };
+    let query = |name: &str, schema: serde_json::Value, description: &str| {
+        json!({
+            "name": name,
+            "in": "query",
+            "schema": schema,
+            "description": description
+        })
+    };
+    let schema = |name: &str| json!({ "$ref": format!("#/components/schemas/{name}") });
+    let array = |items: serde_json::Value| json!({ "type": "array", "items": items });
+    let int32 = json!({ "type": "integer", "format": "int32" });
+    let int64 = json!({ "type": "integer", "format": "int64" });
+    let one_of = |values: &[&str]| json!({ "type": "string", "enum": values });
+    let csv = json!({ "type": "string", "description": "Comma separated ids" });
+
+    let subjects = ["character", "corporation", "alliance", "faction"];
+    let names = query(
+        "names",
+        json!({ "type": "boolean" }),
+        "Wrap the report as `{ data, names }` with the names of the ids it contains",
+    );
+    let filters = vec![
+        query(
+            "from",
+            int64.clone(),
+            "Unix timestamp to report from, inclusive",
+        ),
+        query(
+            "to",
+            int64.clone(),
+            "Unix timestamp to report to, exclusive",
+        ),
+        query(
+            "days",
+            int32.clone(),
+            "Number of days to report, unless `from` is given",
+        ),
+        names.clone(),
+    ];
+    let paging = vec![
+        query("limit", int32.clone(), "Maximum number of rows to return"),
+        query("offset", int32.clone(), "Number of rows to skip"),
+        query(
+            "order",
+            one_of(&["desc", "asc"]),
+            "Order of the rows by count or time, `desc` by default",
+        ),
+    ];
+    let error = json!({
+        "description": "Error",
+        "content": { "application/json": { "schema": schema("Error") } }
+    });
+    let response = |content: serde_json::Value| {
+        json!({
+            "200": {
+                "description": "OK",
+                "content": { "application/json": { "schema": content } }
+            },
+            "400": error,
+            "404": error,
+            "500": error
+        })
+    };
+    let report = |summary: &str, params: Vec<serde_json::Value>, content: serde_json::Value| {
+        let parameters = [params, filters.clone()].concat();
+        let responses = response(content);
+        json!({ "get": { "summary": summary, "parameters": parameters, "responses": responses } })
+    };
+    let list = |summary: &str, params: Vec<serde_json::Value>, items: serde_json::Value| {
+        let parameters = [params, filters.clone(), paging.clone()].concat();
+        let responses = response(json!({
+            "type": "object",
+            "properties": { "total": int64, "rows": array(items) }
+        }));
+        json!({ "get": { "summary": summary, "parameters": parameters, "responses": responses } })
+    };
+    let body = |content: serde_json::Value| {
+        json!({
+            "required": true,
+            "content": { "application/json": { "schema": content } }
+        })
+    };
+
+    let subject = || path("subject", one_of(&subjects));
+    let rtype = || path("rtype", one_of(&["wins", "losses"]));
+    let side = || path("side", one_of(&["aggressor", "defender"]));
+    let id = || path("id", int32.clone());
+
+    json!({
+        "openapi": "3.0.3",
+        "info": { "title": "zkbinfo", "version": env!("CARGO_PKG_VERSION") },
+        "paths": {
+            "/api/friendly/{object}/for/{subject}/{id}": list(
+                "Entities that joined the subject on its kills",
+                vec![path("object", one_of(&subjects)), subject(), id()],
+                schema("Count"),
+            ),
+            "/api/enemy/{object}/for/{subject}/{id}": list(
+                "Entities the subject fought against",
+                vec![path("object", one_of(&subjects)), subject(), id()],
+                schema("Count"),
+            ),
+            "/api/{rtype}/{subject}/{id}": report(
+                "Number of wins or losses and the damage dealt or taken",
+                vec![rtype(), subject(), id()],
+                schema("Total"),
+            ),
+            "/api/{rtype}/{subject}/{id}/ships": list(
+                "Wins or losses per ship type",
+                vec![rtype(), subject(), id()],
+                schema("Count"),
+            ),
+            "/api/{rtype}/{subject}/{id}/systems": list(
+                "Wins or losses per solar system",
+                vec![rtype(), subject(), id()],
+                schema("Count"),
+            ),
+            "/api/lost/ship/{sid}/{subject}/{id}": list(
+                "Losses of a ship type",
+                vec![path("sid", int32.clone()), subject(), id()],
+                schema("Loss"),
+            ),
+            "/api/lost/system/{sid}/{subject}/{id}": list(
+                "Losses in a solar system",
+                vec![path("sid", int32.clone()), subject(), id()],
+                schema("Loss"),
+            ),
+            "/api/hotspots/{area}": list(
+                "Systems, constellations or regions by number of kills",
+                vec![path("area", one_of(&["system", "constellation", "region"]))],
+                schema("Count"),
+            ),
+            "/api/versus/{subject}/{id}/{other}/{oid}": report(
+                "Kills, damage, ship losses, systems and days of two entities fighting each other",
+                vec![
+                    subject(),
+                    id(),
+                    path("other", one_of(&subjects)),
+                    path("oid", int32.clone()),
+                ],
+                schema("Versus"),
+            ),
+            "/api/gang/{subject}/{id}": report(
+                "Attacker counts on the kills of the subject and its members per kill",
+                vec![subject(), id()],
+                schema("GangSize"),
+            ),
+            "/api/killers/ship/{sid}": list(
+                "Attacker ship types on the kills of a ship type",
+                vec![path("sid", int32.clone())],
+                schema("Count"),
+            ),
+            "/api/weapons/{subject}/{id}": list(
+                "Weapon types used by the subject on its kills",
+                vec![subject(), id()],
+                schema("Count"),
+            ),
+            "/api/flown/{subject}/{id}/against/{target}/{tid}": list(
+                "Ship types the subject flew on its kills of the target",
+                vec![
+                    subject(),
+                    id(),
+                    path("target", one_of(&subjects)),
+                    path("tid", int32.clone()),
+                ],
+                schema("Count"),
+            ),
+            "/api/activity/{subject}/{id}": report(
+                "Wins and losses by weekday and hour of the day",
+                vec![
+                    subject(),
+                    id(),
+                    query(
+                        "tz",
+                        int32.clone(),
+                        "Timezone offset from UTC in minutes, `0` by default",
+                    ),
+                ],
+                schema("Activity"),
+            ),
+            "/api/war/{id}/{side}": report(
+                "Kills and ISK destroyed by a side of a war",
+                vec![id(), side()],
+                schema("WarKills"),
+            ),
+            "/api/war/{id}/{side}/pilots": list(
+                "Pilots of a side of a war by kills",
+                vec![id(), side()],
+                schema("Count"),
+            ),
+            "/api/war/{id}/{side}/timeline": report(
+                "Kills by a side of a war per day",
+                vec![id(), side()],
+                array(schema("Day")),
+            ),
+            "/killmail/recent": list(
+                "The newest killmails, optionally of a system, victim ship type or entity",
+                vec![
+                    query("system", int32.clone(), "Solar system id"),
+                    query("ship", int32.clone(), "Ship type id of the victim"),
+                    query("character", int32.clone(), "Victim or attacker character id"),
+                    query("corporation", int32.clone(), "Victim or attacker corporation id"),
+                    query("alliance", int32.clone(), "Victim or attacker alliance id"),
+                    query("faction", int32.clone(), "Victim or attacker faction id"),
+                ],
+                schema("Killmail"),
+            ),
+            "/killmail/stream": {
+                "get": {
+                    "summary": "Server-sent `killmail` events of newly saved killmails",
+                    "parameters": [
+                        query("entities", csv.clone(), "Victim or attacker entity ids"),
+                        query("systems", csv.clone(), "Solar system ids"),
+                        query("ships", csv.clone(), "Ship type ids of the victim"),
+                        query("min_attackers", int32.clone(), "Minimum number of attackers")
+                    ],
+                    "responses": {
+                        "200": {
+                            "description": "Event stream, with `lagged` events when behind",
+                            "content": { "text/event-stream": { "schema": schema("Killmail") } }
+                        },
+                        "400": error
+                    }
+                }
+            },
+            "/killmail/id/{id}": {
+                "get": {
+                    "summary": "A stored killmail",
+                    "parameters": [id(), names],
+                    "responses": response(schema("Killmail"))
+                }
+            },
+            "/killmail/{date}": {
+                "get": {
+                    "summary": "Ids of the killmails of a date",
+                    "parameters": [path("date", json!({ "type": "string", "format": "date" }))],
+                    "responses": response(array(int32.clone()))
+                }
+            },
+            "/killmail/{id}/zkb": {
+                "get": {
+                    "summary": "The zKillboard data of a killmail",
+                    "parameters": [id()],
+                    "responses": response(json!({ "type": "object" }))
+                }
+            },
+            "/killmail/save": {
+                "post": {
+                    "summary": "Save a killmail",
+                    "requestBody": body(json!({ "type": "object" })),
+                    "responses": response(schema("Saved"))
+                }
+            },
+            "/killmail/save/batch": {
+                "post": {
+                    "summary": "Save a batch of killmails",
+                    "requestBody": body(array(json!({ "type": "object" }))),
+                    "responses": response(array(schema("Saved")))
+                }
+            }
+        },
+        "components": {
+            "schemas": {
+                "Total": {
+                    "type": "object",
+                    "properties": { "count": int64, "damage": int64 }
+                },
+                "Count": {
+                    "type": "object",
+                    "properties": { "id": int32, "count": int64 }
+                },
+                "Loss": {
+                    "type": "object",
+                    "properties": {
+                        "killmail_id": int32,
+                        "character_id": int32,
+                        "corporation_id": int32,
+                        "alliance_id": int32,
+                        "ship_type_id": int32,
+                        "damage_taken": int32,
+                        "solar_system_id": int32,
+                        "killmail_time": { "type": "string", "format": "date-time" }
+                    }
+                },
+                "WarKills": {
+                    "type": "object",
+                    "properties": { "kills": int64, "isk": { "type": "number" } }
+                },
+                "Activity": {
+                    "type": "object",
+                    "description": "7x24 counts, rows are weekdays from monday",
+                    "properties": {
+                        "wins": array(array(int64.clone())),
+                        "losses": array(array(int64.clone()))
+                    }
+                },
+                "Versus": {
+                    "type": "object",
+                    "description": "`a` holds the kills of the subject on the other",
+                    "properties": {
+                        "a": schema("Side"),
+                        "b": schema("Side"),
+                        "systems": array(schema("Count")),
+                        "timeline": array(json!({
+                            "type": "object",
+                            "properties": { "day": int64, "a": int64, "b": int64 }
+                        }))
+                    }
+                },
+                "Side": {
+                    "type": "object",
+                    "properties": {
+                        "kills": int64,
+                        "damage": int64,
+                        "ships": array(schema("Count"))
+                    }
+                },
+                "GangSize": {
+                    "type": "object",
+                    "properties": {
+                        "kills": int64,
+                        "solo": { "type": "number", "description": "Share of solo kills" },
+                        "median": { "type": "number" },
+                        "members": {
+                            "type": "number",
+                            "description": "Average attackers of the subject per kill"
+                        },
+                        "sizes": array(json!({
+                            "type": "object",
+                            "properties": { "attackers": int64, "kills": int64 }
+                        }))
+                    }
+                },
+                "Day": {
+                    "type": "object",
+                    "properties": { "day": int64, "kills": int64 }
+                },
+                "Saved": {
+                    "type": "object",
+                    "properties": {
+                        "id": int32,
+                        "status": one_of(&["inserted", "duplicate", "updated"])
+                    }
+                },
+                "Killmail": {
+                    "type": "object",
+                    "description": "The ESI killmail with its zKillboard data"
+                },
+                "Error": {
+                    "type": "object",
+                    "properties": { "status": int32, "error": { "type": "string" } }
+                }
+            }
+        }
+    })
+}
+
 fn env<T: std::str::FromStr>(key: &str, default: T) -> T {
     env::var(key)
         .unwrap_or_default()
@@ -332,10 +1700,16 @@ fn env<T: std::str::FromStr>(key: &str, default: T) -> T {
         .unwrap_or(default)
 }
 
-fn cleanup(api: &mut Api, keep_days: u16) {
-    match api.cleanup(keep_days) {
-        Ok(count) => info!("Clean up performed. Deleted {count} killmails"),
-        Err(err) => error!("Clean up failed: {err}"),
+fn cleanup(api: &Api, keep_days: u16, archive: Option<&Archive>) {
+    match archive {
+        Some(archive) => match api.archive(archive, keep_days) {
+            Ok(count) => info!("Clean up performed. Archived {count} killmails"),
+            Err(err) => error!("Archive failed: {err}"),
+        },
+        None => match api.cleanup(keep_days) {
+            Ok(count) => info!("Clean up performed. Deleted {count} killmails"),
+            Err(err) => error!("Clean up failed: {err}"),
+        },
     }
     match api.remove_dangling_attackers() {
         Ok(count) => info!("Clean up performed. Deleted {count} attackers"),
@@ -345,4 +1719,12 @@ fn cleanup(api: &mut Api, keep_days: u16) {
         Ok(count) => info!("Clean up performed. Deleted {count} victims"),
         Err(err) => error!("Clean up failed: {err}"),
     }
+    match api.remove_dangling_items() {
+        Ok(count) => info!("Clean up performed. Deleted {count} items"),
+        Err(err) => error!("Clean up failed: {err}"),
+    }
+    match api.remove_dangling_zkb() {
+        Ok(count) => info!("Clean up performed. Deleted {count} zkb"),
+        Err(err) => error!("Clean up failed: {err}"),
+    }
 }
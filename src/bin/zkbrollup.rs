@@ -0,0 +1,36 @@
+use docopt::Docopt;
+use serde::Deserialize;
+
+use evetech::models::Api;
+
+const USAGE: &str = "
+Killmail Rollups
+
+Usage:
+  zkbrollup rebuild [--db=<uri>]
+  zkbrollup (-h | --help)
+
+Options:
+  -h --help   Show this screen.
+  --db=<uri>  The killmail database  [default: killmails.db].
+";
+
+#[derive(Debug, Deserialize)]
+struct Args {
+    cmd_rebuild: bool,
+    flag_db: String,
+}
+
+fn main() -> anyhow::Result<()> {
+    let args: Args = Docopt::new(USAGE)
+        .and_then(|d| d.deserialize())
+        .unwrap_or_else(|e| e.exit());
+
+    let api = Api::new(&args.flag_db, 1)?;
+
+    if args.cmd_rebuild {
+        let count = api.rebuild_rollups()?;
+        println!("Rebuilt rollups from {count} killmails");
+    }
+    Ok(())
+}
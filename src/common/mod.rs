@@ -2,6 +2,7 @@ pub mod names;
 pub mod position;
 pub mod search_result;
 pub mod status;
+pub mod units;
 pub mod vector;
 
 pub use names::{Names, Category};
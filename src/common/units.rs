@@ -0,0 +1,54 @@
+pub const AU: f64 = 149_597_870_700.0;
+pub const KM: f64 = 1_000.0;
+
+pub fn au_to_km(au: f64) -> f64 {
+    au * AU / KM
+}
+
+pub fn km_to_au(km: f64) -> f64 {
+    km * KM / AU
+}
+
+pub fn to_au(meters: f64) -> f64 {
+    meters / AU
+}
+
+pub fn to_km(meters: f64) -> f64 {
+    meters / KM
+}
+
+pub fn from_au(au: f64) -> f64 {
+    au * AU
+}
+
+pub fn from_km(km: f64) -> f64 {
+    km * KM
+}
+
+pub fn format(meters: f64) -> String {
+    if meters < 0.1 * AU {
+        format!("{:.0} km", to_km(meters))
+    } else {
+        format!("{:.2} AU", to_au(meters))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn convert() {
+        assert_relative_eq!(au_to_km(1.0), 149_597_870.7);
+        assert_relative_eq!(km_to_au(149_597_870.7), 1.0);
+        assert_relative_eq!(to_au(from_au(14.3)), 14.3);
+        assert_relative_eq!(to_km(from_km(2500.0)), 2500.0);
+    }
+
+    #[test]
+    fn format_distance() {
+        assert_eq!(format(150_000.0), "150 km");
+        assert_eq!(format(from_au(14.3)), "14.30 AU");
+    }
+}
@@ -39,6 +39,18 @@ impl Vector {
         // https://ru.onlinemschool.com/math/library/vector/angl/
         Self::scalar_product(a, b) / a.length() / b.length()
     }
+
+    pub fn point_at(&self, k: f64) -> Position {
+        Position::new(
+            self.beg.x + k * self.dx,
+            self.beg.y + k * self.dy,
+            self.beg.z + k * self.dz,
+        )
+    }
+
+    pub fn point_at_distance(&self, distance: f64) -> Position {
+        self.point_at(distance / self.length())
+    }
 }
 
 impl fmt::Display for Vector {
@@ -83,4 +95,14 @@ mod tests {
         assert_relative_eq!(Vector::cos_angl(&one, &two), 0.9925833339709302);
     }
 
+    #[test]
+    fn point_at() {
+        let one = Vector::new(Position::new(1.0, 1.0, 1.0), Position::new(3.0, 1.0, 1.0));
+
+        assert_eq!(one.point_at(0.0), Position::new(1.0, 1.0, 1.0));
+        assert_eq!(one.point_at(0.5), Position::new(2.0, 1.0, 1.0));
+        assert_eq!(one.point_at(2.0), Position::new(5.0, 1.0, 1.0));
+        assert_eq!(one.point_at_distance(3.0), Position::new(4.0, 1.0, 1.0));
+    }
+
 }
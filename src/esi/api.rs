@@ -1,11 +1,13 @@
 use log::{debug, error};
-use reqwest::{header, Client, RequestBuilder, StatusCode};
+use reqwest::{header, Client, RequestBuilder, Response, StatusCode};
 use serde::{Deserialize, Serialize};
 
-use crate::{common, esi::ApiClient};
+use crate::{common, esi::ApiClient, war};
 
 use std::fmt::Debug;
 
+const X_PAGES: &str = "x-pages";
+
 pub enum Uid {
     Empty,
     Id(i32),
@@ -46,6 +48,66 @@ impl EveApi {
         let object = self.post(uri, names).await?;
         Ok(object)
     }
+
+    pub async fn war_killmails(&self, id: i32) -> anyhow::Result<Vec<war::WarKillmail>> {
+        let uri = war::WarKillmail::uri(&Uid::Id(id))?;
+        let mut killmails = Vec::new();
+        let mut page = 1;
+        loop {
+            let (object, pages) = self
+                .get_page::<Vec<war::WarKillmail>>(format!("{uri}&page={page}"))
+                .await?;
+            killmails.extend(object);
+            if page >= pages {
+                break;
+            }
+            page += 1;
+        }
+        Ok(killmails)
+    }
+
+    async fn get_page<T>(&self, url: String) -> anyhow::Result<(T, u32)>
+    where
+        T: Debug + for<'de> Deserialize<'de>,
+    {
+        debug!("<- {}", url);
+        let response = self
+            .client
+            .get(url)
+            .header(header::ACCEPT, "application/json")
+            .header(header::CACHE_CONTROL, "no-cache")
+            .send()
+            .await
+            .inspect_err(|e| error!("{:?}", e))?;
+
+        // paginated ESI routes report the number of pages in the header
+        let pages = response
+            .headers()
+            .get(X_PAGES)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<u32>().ok())
+            .unwrap_or(1);
+        let object = Self::json(response).await?;
+        Ok((object, pages))
+    }
+
+    async fn json<T>(response: Response) -> anyhow::Result<T>
+    where
+        T: Debug + for<'de> Deserialize<'de>,
+    {
+        let status = response.status();
+        if status == StatusCode::OK {
+            let object = response
+                .json::<T>()
+                .await
+                .inspect_err(|e| error!("{:?}", e))?;
+            debug!("-> {:?}", object);
+            Ok(object)
+        } else {
+            error!("{}", status);
+            Err(anyhow::anyhow!(status))
+        }
+    }
 }
 
 impl ApiClient for EveApi {
@@ -86,19 +148,6 @@ impl ApiClient for EveApi {
         T: Debug + for<'de> Deserialize<'de>,
     {
         let response = request.send().await.inspect_err(|e| error!("{:?}", e))?;
-
-        let status = response.status();
-        if status == StatusCode::OK {
-            let object = response
-                .json::<T>()
-                .await
-                .inspect_err(|e| error!("{:?}", e))?;
-            debug!("-> {:?}", object);
-            Ok(object)
-        } else {
-            let error = format!("{}", status);
-            error!("{}", error);
-            Err(anyhow::anyhow!(error))
-        }
+        Self::json(response).await
     }
 }
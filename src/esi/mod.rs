@@ -12,6 +12,7 @@ pub const STATUS: &'static str = "https://esi.evetech.net/latest/status";
 pub const MARKETS: &'static str = "https://esi.evetech.net/latest/markets";
 pub const KILLMAILS: &'static str = "https://esi.evetech.net/latest/killmails";
 pub const UNIVERSE: &'static str = "https://esi.evetech.net/latest/universe";
+pub const WARS: &str = "https://esi.evetech.net/latest/wars";
 
 pub const ALLIANCES: &'static str = "https://esi.evetech.net/latest/alliances";
 pub const CORPORATIONS: &'static str = "https://esi.evetech.net/latest/corporations";
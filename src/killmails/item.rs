@@ -1,4 +1,3 @@
-
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Default)]
 pub struct Item {
     pub flag: i32,
@@ -6,6 +5,7 @@ pub struct Item {
     pub quantity_destroyed: Option<u64>,
     pub quantity_dropped: Option<u64>,
     pub singleton: i32,
+    pub items: Option<Vec<Item>>,
 }
 
 #[cfg(test)]
@@ -27,6 +27,40 @@ mod tests {
         assert_eq!(item.quantity_destroyed, Some(1));
         assert_eq!(item.quantity_dropped, None);
         assert_eq!(item.singleton, 0);
+        assert_eq!(item.items, None);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_container() -> anyhow::Result<()> {
+        let item = serde_json::from_str::<Item>(
+            r##"
+            {
+                "flag": 5,
+                "item_type_id": 3467,
+                "items": [
+                    {
+                        "flag": 0,
+                        "item_type_id": 34,
+                        "quantity_dropped": 1000,
+                        "singleton": 0
+                    }
+                ],
+                "quantity_destroyed": 1,
+                "singleton": 0
+            }"##,
+        )?;
+        assert_eq!(
+            item.items,
+            Some(vec![Item {
+                flag: 0,
+                item_type_id: 34,
+                quantity_destroyed: None,
+                quantity_dropped: Some(1000),
+                singleton: 0,
+                items: None
+            }])
+        );
         Ok(())
     }
 }
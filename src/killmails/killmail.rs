@@ -1,11 +1,12 @@
 
-use super::{attacker::Attacker, victim::Victim};
+use super::{attacker::Attacker, item::Item, victim::Victim, zkb::Zkb};
 use crate::esi::api::Uid;
 use crate::esi::api::Uri;
 use crate::esi::PARAM;
 use crate::esi::KILLMAILS;
 
 use anyhow::anyhow;
+use std::collections::BTreeSet;
 
 impl Uri for Killmail {
     fn uri(id: &Uid) -> anyhow::Result<String> {
@@ -26,6 +27,86 @@ pub struct Killmail {
     pub war_id: Option<i32>,
     pub attackers: Vec<Attacker>,
     pub victim: Victim,
+    pub zkb: Option<Zkb>,
+}
+impl Killmail {
+    pub fn ids(&self) -> Vec<i32> {
+        let mut ids = BTreeSet::from([self.solar_system_id, self.victim.ship_type_id]);
+        ids.extend(
+            [
+                self.victim.character_id,
+                self.victim.corporation_id,
+                self.victim.alliance_id,
+                self.victim.faction_id,
+            ]
+            .into_iter()
+            .flatten(),
+        );
+        for attacker in &self.attackers {
+            ids.extend(
+                [
+                    attacker.character_id,
+                    attacker.corporation_id,
+                    attacker.alliance_id,
+                    attacker.faction_id,
+                    attacker.ship_type_id,
+                    attacker.weapon_type_id,
+                ]
+                .into_iter()
+                .flatten(),
+            );
+        }
+        Self::item_ids(self.victim.items.as_deref().unwrap_or_default(), &mut ids);
+        ids.remove(&0);
+        ids.into_iter().collect()
+    }
+
+    pub fn entities(&self) -> Vec<i32> {
+        let mut ids = BTreeSet::new();
+        ids.extend(
+            [
+                self.victim.character_id,
+                self.victim.corporation_id,
+                self.victim.alliance_id,
+                self.victim.faction_id,
+            ]
+            .into_iter()
+            .flatten(),
+        );
+        for attacker in &self.attackers {
+            ids.extend(
+                [
+                    attacker.character_id,
+                    attacker.corporation_id,
+                    attacker.alliance_id,
+                    attacker.faction_id,
+                ]
+                .into_iter()
+                .flatten(),
+            );
+        }
+        ids.remove(&0);
+        ids.into_iter().collect()
+    }
+
+    pub fn timestamp(&self) -> anyhow::Result<i64> {
+        chrono::DateTime::parse_from_rfc3339(&self.killmail_time)
+            .map(|time| time.timestamp())
+            .map_err(|e| {
+                anyhow!(
+                    "The killmail {} has invalid time '{}': {e}",
+                    self.killmail_id,
+                    self.killmail_time
+                )
+            })
+    }
+
+    fn item_ids(items: &[Item], ids: &mut BTreeSet<i32>) {
+        for item in items {
+            ids.insert(item.item_type_id);
+            Self::item_ids(item.items.as_deref().unwrap_or_default(), ids);
+        }
+    }
 }
 
 #[cfg(test)]
@@ -161,18 +242,78 @@ mod tests {
                         item_type_id: 31724,
                         quantity_destroyed: Some(1),
                         quantity_dropped: None,
-                        singleton: 0
+                        singleton: 0,
+                        items: None
                     },
                     Item {
                         flag: 21,
                         item_type_id: 3831,
                         quantity_destroyed: None,
                         quantity_dropped: Some(1),
-                        singleton: 0
+                        singleton: 0,
+                        items: None
                     }
                 ])
             }
         );
+        assert_eq!(killmail.zkb, None);
+        Ok(())
+    }
+
+    #[test]
+    fn ids() -> anyhow::Result<()> {
+        let killmail = serde_json::from_str::<Killmail>(JSON)?;
+        assert_eq!(
+            killmail.ids(),
+            vec![
+                3831, 31724, 34495, 34580, 81008, 500024, 1000274, 3019581, 3019582, 30004563,
+                98316235, 1900696668, 2120326223
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn entities() -> anyhow::Result<()> {
+        let killmail = serde_json::from_str::<Killmail>(JSON)?;
+        assert_eq!(
+            killmail.entities(),
+            vec![500024, 1000274, 3019581, 3019582, 98316235, 1900696668, 2120326223]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parse_killstream() -> anyhow::Result<()> {
+        let json = r##"
+        {
+            "attackers": [],
+            "killmail_id": 120480909,
+            "killmail_time": "2024-08-28T02:19:31Z",
+            "solar_system_id": 30004348,
+            "victim": {
+                "damage_taken": 420,
+                "ship_type_id": 670
+            },
+            "zkb": {
+                "locationID": 40275648,
+                "hash": "9c01e82d5a65818c816a72e6bcc24dd045dde2f8",
+                "fittedValue": 10000,
+                "droppedValue": 0,
+                "destroyedValue": 10000,
+                "totalValue": 10000,
+                "points": 1,
+                "npc": false,
+                "solo": false,
+                "awox": false,
+                "labels": ["cat:6", "pvp"]
+            }
+        }"##;
+        let killmail = serde_json::from_str::<Killmail>(json)?;
+        let zkb = killmail.zkb.unwrap();
+        assert_eq!(zkb.hash, "9c01e82d5a65818c816a72e6bcc24dd045dde2f8");
+        assert_eq!(zkb.total_value, 10000.0);
+        assert_eq!(zkb.labels, vec!["cat:6", "pvp"]);
         Ok(())
     }
 
@@ -2,8 +2,10 @@ pub mod attacker;
 pub mod item;
 pub mod killmail;
 pub mod victim;
+pub mod zkb;
 
 pub use attacker::Attacker;
 pub use item::Item;
 pub use killmail::Killmail;
 pub use victim::Victim;
+pub use zkb::Zkb;
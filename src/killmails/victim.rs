@@ -61,14 +61,16 @@ mod tests {
                     item_type_id: 31724,
                     quantity_destroyed: Some(1),
                     quantity_dropped: None,
-                    singleton: 0
+                    singleton: 0,
+                    items: None
                 },
                 Item {
                     flag: 185,
                     item_type_id: 81144,
                     quantity_destroyed: Some(1924),
                     quantity_dropped: None,
-                    singleton: 0
+                    singleton: 0,
+                    items: None
                 }
             ])
         );
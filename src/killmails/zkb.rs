@@ -0,0 +1,71 @@
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Zkb {
+    pub hash: String,
+    #[serde(default)]
+    pub total_value: f64,
+    #[serde(default)]
+    pub fitted_value: f64,
+    #[serde(default)]
+    pub dropped_value: f64,
+    #[serde(default)]
+    pub points: i32,
+    #[serde(default)]
+    pub npc: bool,
+    #[serde(default)]
+    pub solo: bool,
+    #[serde(default)]
+    pub awox: bool,
+    #[serde(default)]
+    pub labels: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    const JSON: &str = r##"
+        {
+            "locationID": 40275648,
+            "hash": "9c01e82d5a65818c816a72e6bcc24dd045dde2f8",
+            "fittedValue": 10000,
+            "droppedValue": 2540.5,
+            "destroyedValue": 62834.43,
+            "totalValue": 65374.93,
+            "points": 1,
+            "npc": false,
+            "solo": true,
+            "awox": false,
+            "labels": ["cat:6", "solo", "pvp", "loc:lowsec"],
+            "href": "https://esi.evetech.net/v1/killmails/120480909/9c01e82d5a65818c816a72e6bcc24dd045dde2f8/"
+        }"##;
+
+    #[test]
+    fn parse() -> anyhow::Result<()> {
+        let zkb = serde_json::from_str::<Zkb>(JSON)?;
+        assert_eq!(zkb.hash, "9c01e82d5a65818c816a72e6bcc24dd045dde2f8");
+        assert_eq!(zkb.total_value, 65374.93);
+        assert_eq!(zkb.fitted_value, 10000.0);
+        assert_eq!(zkb.dropped_value, 2540.5);
+        assert_eq!(zkb.points, 1);
+        assert!(!zkb.npc);
+        assert!(zkb.solo);
+        assert!(!zkb.awox);
+        assert_eq!(zkb.labels, vec!["cat:6", "solo", "pvp", "loc:lowsec"]);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_minimal() -> anyhow::Result<()> {
+        let zkb = serde_json::from_str::<Zkb>(
+            r#"{"locationID": 40275648, "hash": "9c01e82d5a65818c816a72e6bcc24dd045dde2f8"}"#,
+        )?;
+        assert_eq!(
+            zkb,
+            Zkb {
+                hash: String::from("9c01e82d5a65818c816a72e6bcc24dd045dde2f8"),
+                ..Default::default()
+            }
+        );
+        Ok(())
+    }
+}
@@ -12,3 +12,4 @@ pub mod market;
 pub mod models;
 pub mod schema;
 pub mod universe;
+pub mod war;
@@ -1,24 +1,164 @@
-use std::collections::HashMap;
-use std::sync::Mutex;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 
+use crate::common;
 use crate::killmails;
 use crate::models;
+use crate::models::archive::Archive;
+use crate::models::pool::{self, DbConnection, Pool, PooledConnection};
+use crate::models::rollup::{self, Rollups};
 use crate::schema;
+use crate::war;
 
-use chrono::NaiveDateTime;
-use chrono::Timelike;
+use chrono::FixedOffset;
 use diesel::dsl::count_star;
 use diesel::prelude::*;
-use diesel::sqlite::SqliteConnection;
 
-const DATETIME: &str = "%Y-%m-%dT%H:%M:%SZ";
+const ARCHIVE_CHUNK: usize = 500;
+const NAMES_CHUNK: usize = 500;
 
+fn since(duration: chrono::Duration) -> i64 {
+    (chrono::Utc::now() - duration).timestamp()
+}
+
+type Filter<QS> = Box<
+    dyn BoxableExpression<
+        QS,
+        <DbConnection as Connection>::Backend,
+        SqlType = diesel::sql_types::Bool,
+    >,
+>;
+
+fn attacker_filter<QS>(rq: SubjectType) -> Filter<QS>
+where
+    schema::attackers::character_id: SelectableExpression<QS>,
+    schema::attackers::corporation_id: SelectableExpression<QS>,
+    schema::attackers::alliance_id: SelectableExpression<QS>,
+    schema::attackers::faction_id: SelectableExpression<QS>,
+{
+    use schema::attackers;
+
+    match rq {
+        SubjectType::Character(id) => Box::new(attackers::character_id.eq(id)),
+        SubjectType::Corporation(id) => Box::new(attackers::corporation_id.eq(id)),
+        SubjectType::Alliance(id) => Box::new(attackers::alliance_id.eq(id)),
+        SubjectType::Faction(id) => Box::new(attackers::faction_id.eq(id)),
+    }
+}
+
+fn victim_filter<QS>(rq: SubjectType) -> Filter<QS>
+where
+    schema::victims::character_id: SelectableExpression<QS>,
+    schema::victims::corporation_id: SelectableExpression<QS>,
+    schema::victims::alliance_id: SelectableExpression<QS>,
+    schema::victims::faction_id: SelectableExpression<QS>,
+{
+    use schema::victims;
+
+    match rq {
+        SubjectType::Character(id) => Box::new(victims::character_id.eq(id)),
+        SubjectType::Corporation(id) => Box::new(victims::corporation_id.eq(id)),
+        SubjectType::Alliance(id) => Box::new(victims::alliance_id.eq(id)),
+        SubjectType::Faction(id) => Box::new(victims::faction_id.eq(id)),
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
 pub enum SubjectType {
     Character(i32),
     Corporation(i32),
     Alliance(i32),
     Faction(i32),
 }
+impl SubjectType {
+    fn entity(&self) -> rollup::Entity {
+        match self {
+            SubjectType::Character(id) => (rollup::CHARACTER, *id),
+            SubjectType::Corporation(id) => (rollup::CORPORATION, *id),
+            SubjectType::Alliance(id) => (rollup::ALLIANCE, *id),
+            SubjectType::Faction(id) => (rollup::FACTION, *id),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Order {
+    #[default]
+    Desc,
+    Asc,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct Page {
+    pub limit: Option<i64>,
+    pub offset: i64,
+    pub order: Order,
+}
+impl Page {
+    pub fn new(limit: Option<i64>, offset: i64, order: Order) -> Self {
+        Self {
+            limit,
+            offset,
+            order,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Paged<T> {
+    pub rows: Vec<T>,
+    pub total: i64,
+}
+
+/// Kills and losses by weekday (monday first) and hour of the day.
+#[derive(Serialize, Debug, PartialEq, Clone, Copy, Default)]
+pub struct Activity {
+    pub wins: [[i64; 24]; 7],
+    pub losses: [[i64; 24]; 7],
+}
+
+/// Attacker counts on the kills of a subject.
+#[derive(Serialize, Debug, PartialEq, Clone, Default)]
+pub struct Gang {
+    pub kills: i64,
+    pub sizes: Vec<(i64, i64)>,
+    pub solo: f64,
+    pub median: f64,
+    pub members: f64,
+}
+
+/// Kills of one entity by another.
+#[derive(Serialize, Debug, PartialEq, Clone, Default)]
+pub struct Clash {
+    pub kills: i64,
+    pub damage: i64,
+    pub ships: Vec<(i32, i64)>,
+}
+
+/// Solar system, timestamp and victim ship type of a kill.
+type Kill = (i32, i64, i32);
+
+/// Head to head record of two entities, `a` holding the kills of the first on the second.
+#[derive(Serialize, Debug, PartialEq, Clone, Default)]
+pub struct Versus {
+    pub a: Clash,
+    pub b: Clash,
+    pub systems: Vec<(i32, i64)>,
+    pub timeline: Vec<(i64, i64, i64)>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct KillFilter {
+    pub system: Option<i32>,
+    pub ship: Option<i32>,
+    pub subjects: Vec<SubjectType>,
+}
+
+pub enum Area {
+    System,
+    Constellation,
+    Region,
+}
 
 pub enum ObjectType {
     Character,
@@ -27,538 +167,1603 @@ pub enum ObjectType {
     Faction,
 }
 
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct Interval {
+    pub from: Option<i64>,
+    pub to: Option<i64>,
+}
+impl Interval {
+    pub fn new(from: Option<i64>, to: Option<i64>) -> Self {
+        Self { from, to }
+    }
+
+    pub fn days(days: u16) -> Self {
+        let from = rollup::day(since(chrono::Duration::days(days.into())));
+        Self::new(Some(from), None)
+    }
+
+    fn start(&self) -> i64 {
+        self.from.unwrap_or(i64::MIN)
+    }
+
+    fn end(&self) -> i64 {
+        self.to.unwrap_or(i64::MAX)
+    }
+
+    fn whole_days(&self) -> Option<(i64, i64)> {
+        let aligned = |time: Option<i64>| time.is_none_or(|time| time == rollup::day(time));
+        (aligned(self.from) && aligned(self.to)).then(|| (self.start(), self.end()))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum SaveStatus {
+    Inserted,
+    Duplicate,
+    Updated,
+}
+
 pub struct Api {
-    conn: Mutex<SqliteConnection>,
+    writer: Pool,
+    readers: Pool,
 }
 impl Api {
-    pub fn new(conn: SqliteConnection) -> Self {
-        Self {
-            conn: Mutex::new(conn),
+    pub fn new(uri: &str, readers: u32) -> anyhow::Result<Self> {
+        Ok(Self {
+            writer: pool::writer(uri)?,
+            readers: pool::readers(uri, readers)?,
+        })
+    }
+
+    fn writer(&self) -> anyhow::Result<PooledConnection> {
+        self.writer.get().map_err(|e| anyhow::anyhow!("{e}"))
+    }
+
+    fn reader(&self) -> anyhow::Result<PooledConnection> {
+        self.readers.get().map_err(|e| anyhow::anyhow!("{e}"))
+    }
+
+    pub fn save(&self, killmail: &killmails::killmail::Killmail) -> anyhow::Result<SaveStatus> {
+        self.writer().and_then(|mut conn| {
+            conn.transaction::<_, anyhow::Error, _>(|conn| Self::upsert(conn, killmail))
+        })
+    }
+
+    pub fn save_batch(
+        &self,
+        killmails: &[killmails::killmail::Killmail],
+    ) -> anyhow::Result<Vec<(i32, SaveStatus)>> {
+        self.writer().and_then(|mut conn| {
+            conn.transaction::<_, anyhow::Error, _>(|conn| {
+                let mut result = Vec::new();
+                for killmail in killmails {
+                    let status = Self::upsert(conn, killmail)?;
+                    result.push((killmail.killmail_id, status));
+                }
+                Ok(result)
+            })
+        })
+    }
+
+    fn upsert(
+        conn: &mut DbConnection,
+        killmail: &killmails::killmail::Killmail,
+    ) -> anyhow::Result<SaveStatus> {
+        use diesel::dsl::{exists, select};
+
+        let id = killmail.killmail_id;
+        let found = select(exists(
+            schema::killmails::table.filter(schema::killmails::killmail_id.eq(id)),
+        ))
+        .get_result::<bool>(conn)?;
+        if !found {
+            Self::insert(conn, killmail)?;
+            return Ok(SaveStatus::Inserted);
+        }
+
+        let mut status = SaveStatus::Duplicate;
+        if let Some(zkb) = &killmail.zkb {
+            let zkb = models::zkb::Zkb::from((id, zkb));
+            let stored = schema::zkb::table
+                .filter(schema::zkb::killmail_id.eq(id))
+                .first::<models::zkb::Zkb>(conn)
+                .optional()?;
+            if stored.as_ref() != Some(&zkb) {
+                diesel::insert_into(schema::zkb::table)
+                    .values(&zkb)
+                    .on_conflict(schema::zkb::killmail_id)
+                    .do_update()
+                    .set(&zkb)
+                    .execute(conn)?;
+                status = SaveStatus::Updated;
+            }
+        }
+        if let Some(items) = &killmail.victim.items {
+            let stored = schema::items::table
+                .filter(schema::items::killmail_id.eq(id))
+                .count()
+                .get_result::<i64>(conn)?;
+            if stored == 0 {
+                diesel::insert_into(schema::items::table)
+                    .values(models::item::Item::flatten(id, items))
+                    .execute(conn)?;
+                status = SaveStatus::Updated;
+            }
         }
+        if let Some(position) = &killmail.victim.position {
+            use schema::victims::dsl::*;
+
+            let updated = diesel::update(
+                victims
+                    .filter(killmail_id.eq(id))
+                    .filter(position_x.is_null()),
+            )
+            .set((
+                position_x.eq(position.x),
+                position_y.eq(position.y),
+                position_z.eq(position.z),
+            ))
+            .execute(conn)?;
+            if updated > 0 {
+                status = SaveStatus::Updated;
+            }
+        }
+        Ok(status)
     }
 
-    pub fn save(&mut self, killmail: &killmails::killmail::Killmail) -> anyhow::Result<i32> {
-        self.conn
-            .lock()
-            .map_err(|e| anyhow::anyhow!("{e}"))
-            .and_then(|mut conn| {
-                conn.transaction::<_, _, _>(|conn| {
-                    let id = killmail.killmail_id;
-                    diesel::insert_into(schema::killmails::table)
-                        .values(models::killmail::Killmail::from(killmail))
-                        .execute(conn)?;
-                    diesel::insert_into(schema::victims::table)
-                        .values(models::victim::Victim::from((id, &killmail.victim)))
+    fn insert(
+        conn: &mut DbConnection,
+        killmail: &killmails::killmail::Killmail,
+    ) -> anyhow::Result<()> {
+        let id = killmail.killmail_id;
+        let row = models::killmail::Killmail::try_from(killmail)?;
+        let victim = models::victim::Victim::from((id, &killmail.victim));
+        let attackers = killmail
+            .attackers
+            .iter()
+            .enumerate()
+            .map(|(index, attacker)| models::attacker::Attacker::from((id, index as i32, attacker)))
+            .collect::<Vec<models::attacker::Attacker>>();
+
+        let mut rollups = Rollups::default();
+        rollups.add(&row, Some(&victim), &attackers, 1);
+        rollups.apply(conn)?;
+
+        diesel::insert_into(schema::killmails::table)
+            .values(row)
+            .execute(conn)?;
+        diesel::insert_into(schema::victims::table)
+            .values(victim)
+            .execute(conn)?;
+        diesel::insert_into(schema::attackers::table)
+            .values(attackers)
+            .execute(conn)?;
+        if let Some(items) = &killmail.victim.items {
+            diesel::insert_into(schema::items::table)
+                .values(models::item::Item::flatten(id, items))
+                .execute(conn)?;
+        }
+        if let Some(zkb) = &killmail.zkb {
+            diesel::insert_into(schema::zkb::table)
+                .values(models::zkb::Zkb::from((id, zkb)))
+                .execute(conn)?;
+        }
+        Ok(())
+    }
+
+    pub fn load(&self, id: i32) -> anyhow::Result<killmails::killmail::Killmail> {
+        self.reader()
+            .and_then(|mut conn| Self::assemble(&mut conn, id))
+    }
+
+    fn assemble(conn: &mut DbConnection, id: i32) -> anyhow::Result<killmails::killmail::Killmail> {
+        let killmail = schema::killmails::table
+            .filter(schema::killmails::killmail_id.eq(id))
+            .first::<models::killmail::Killmail>(conn)?;
+
+        let attackers = schema::attackers::table
+            .filter(schema::attackers::killmail_id.eq(id))
+            .order(schema::attackers::attacker_index)
+            .load::<models::attacker::Attacker>(conn)?
+            .into_iter()
+            .map(|attacker| attacker.into())
+            .collect();
+
+        let items = schema::items::table
+            .filter(schema::items::killmail_id.eq(id))
+            .order(schema::items::item_index)
+            .load::<models::item::Item>(conn)?;
+
+        let mut victim: killmails::victim::Victim = schema::victims::table
+            .filter(schema::victims::killmail_id.eq(id))
+            .first::<models::victim::Victim>(conn)?
+            .into();
+        victim.items = models::item::Item::nest(&items);
+
+        let zkb = schema::zkb::table
+            .filter(schema::zkb::killmail_id.eq(id))
+            .first::<models::zkb::Zkb>(conn)
+            .optional()?
+            .map(|zkb| zkb.into());
+
+        Ok(killmails::killmail::Killmail {
+            killmail_id: id,
+            killmail_time: killmail.killmail_time,
+            solar_system_id: killmail.solar_system_id,
+            moon_id: killmail.moon_id.map(|x| x.try_into().ok()).flatten(),
+            war_id: killmail.war_id.map(|x| x.try_into().ok()).flatten(),
+            attackers,
+            victim,
+            zkb,
+        })
+    }
+
+    pub fn kills(
+        &self,
+        filter: &KillFilter,
+        interval: Interval,
+        page: Page,
+    ) -> anyhow::Result<Paged<killmails::killmail::Killmail>> {
+        use schema::{attackers, killmails, victims};
+
+        let query = || {
+            let mut query = killmails::table
+                .inner_join(victims::table.on(victims::killmail_id.eq(killmails::killmail_id)))
+                .filter(killmails::killmail_timestamp.ge(interval.start()))
+                .filter(killmails::killmail_timestamp.lt(interval.end()))
+                .into_boxed();
+            if let Some(system) = filter.system {
+                query = query.filter(killmails::solar_system_id.eq(system));
+            }
+            if let Some(ship) = filter.ship {
+                query = query.filter(victims::ship_type_id.eq(ship));
+            }
+            for subject in &filter.subjects {
+                let attacked = attackers::table.select(attackers::killmail_id);
+                query = match *subject {
+                    SubjectType::Character(id) => query.filter(
+                        victims::character_id.eq(id).or(killmails::killmail_id
+                            .eq_any(attacked.filter(attackers::character_id.eq(id)))),
+                    ),
+                    SubjectType::Corporation(id) => query.filter(
+                        victims::corporation_id.eq(id).or(killmails::killmail_id
+                            .eq_any(attacked.filter(attackers::corporation_id.eq(id)))),
+                    ),
+                    SubjectType::Alliance(id) => query.filter(
+                        victims::alliance_id.eq(id).or(killmails::killmail_id
+                            .eq_any(attacked.filter(attackers::alliance_id.eq(id)))),
+                    ),
+                    SubjectType::Faction(id) => query.filter(
+                        victims::faction_id.eq(id).or(killmails::killmail_id
+                            .eq_any(attacked.filter(attackers::faction_id.eq(id)))),
+                    ),
+                };
+            }
+            query
+        };
+
+        self.reader().and_then(|mut conn| {
+            let total = query().count().get_result::<i64>(&mut *conn)?;
+            let ids = query().select(killmails::killmail_id);
+            let ids = match page.order {
+                Order::Desc => ids.order((
+                    killmails::killmail_timestamp.desc(),
+                    killmails::killmail_id.desc(),
+                )),
+                Order::Asc => ids.order((
+                    killmails::killmail_timestamp.asc(),
+                    killmails::killmail_id.asc(),
+                )),
+            };
+            let rows = ids
+                .limit(page.limit.unwrap_or(i64::MAX))
+                .offset(page.offset)
+                .load::<i32>(&mut *conn)?
+                .into_iter()
+                .map(|id| Self::assemble(&mut conn, id))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            Ok(Paged { rows, total })
+        })
+    }
+
+    pub fn zkb(&self, id: i32) -> anyhow::Result<killmails::Zkb> {
+        self.reader().and_then(|mut conn| {
+            schema::zkb::table
+                .filter(schema::zkb::killmail_id.eq(id))
+                .first::<models::zkb::Zkb>(&mut *conn)
+                .map(|zkb| zkb.into())
+                .map_err(anyhow::Error::from)
+        })
+    }
+
+    pub fn save_names(&self, names: &[common::Names]) -> anyhow::Result<usize> {
+        let fetched_at = chrono::Utc::now().timestamp();
+        self.writer().and_then(|mut conn| {
+            conn.transaction::<_, anyhow::Error, _>(|conn| {
+                let mut count = 0;
+                for names in names {
+                    let name = models::name::Name::from((names, fetched_at));
+                    count += diesel::insert_into(schema::names::table)
+                        .values(&name)
+                        .on_conflict(schema::names::id)
+                        .do_update()
+                        .set(&name)
                         .execute(conn)?;
-                    for attacker in &killmail.attackers {
-                        diesel::insert_into(schema::attackers::table)
-                            .values(models::attacker::Attacker::from((id, attacker)))
-                            .execute(conn)?;
-                    }
-                    Ok(id)
-                })
+                }
+                Ok(count)
             })
+        })
     }
 
-    pub fn load(&mut self, id: i32) -> anyhow::Result<killmails::killmail::Killmail> {
-        self.conn
-            .lock()
-            .map_err(|e| anyhow::anyhow!("{e}"))
-            .and_then(|mut conn| {
-                let killmail = schema::killmails::table
-                    .filter(schema::killmails::killmail_id.eq(id))
-                    .first::<models::killmail::Killmail>(&mut *conn)?;
-
-                let attackers = schema::attackers::table
-                    .filter(schema::attackers::killmail_id.eq(id))
-                    .load::<models::attacker::Attacker>(&mut *conn)?
-                    .into_iter()
-                    .map(|attacker| attacker.into())
-                    .collect();
-
-                let victim = schema::victims::table
-                    .filter(schema::victims::killmail_id.eq(id))
-                    .first::<models::victim::Victim>(&mut *conn)?
-                    .into();
-
-                Ok(killmails::killmail::Killmail {
-                    killmail_id: id,
-                    killmail_time: killmail.killmail_time,
-                    solar_system_id: killmail.solar_system_id,
-                    moon_id: killmail.moon_id.map(|x| x.try_into().ok()).flatten(),
-                    war_id: killmail.war_id.map(|x| x.try_into().ok()).flatten(),
-                    attackers,
-                    victim,
-                })
+    pub fn names(&self, ids: &[i32]) -> anyhow::Result<HashMap<i32, String>> {
+        use schema::names::dsl::*;
+
+        self.reader().and_then(|mut conn| {
+            let mut result = HashMap::new();
+            for chunk in ids.chunks(NAMES_CHUNK) {
+                result.extend(
+                    names
+                        .filter(id.eq_any(chunk))
+                        .select((id, name))
+                        .load::<(i32, String)>(&mut *conn)?,
+                );
+            }
+            Ok(result)
+        })
+    }
+
+    pub fn unnamed(&self, ids: &[i32], max_age: chrono::Duration) -> anyhow::Result<Vec<i32>> {
+        use schema::names::dsl::*;
+
+        let cutoff = since(max_age);
+        self.reader().and_then(|mut conn| {
+            let mut fresh = HashSet::new();
+            for chunk in ids.chunks(NAMES_CHUNK) {
+                fresh.extend(
+                    names
+                        .filter(id.eq_any(chunk))
+                        .filter(fetched_at.ge(cutoff))
+                        .select(id)
+                        .load::<i32>(&mut *conn)?,
+                );
+            }
+            Ok(ids
+                .iter()
+                .filter(|x| **x != 0 && !fresh.contains(*x))
+                .copied()
+                .collect())
+        })
+    }
+
+    pub fn stored_ids(&self) -> anyhow::Result<Vec<i32>> {
+        use schema::{attackers, items, killmails, locations, victims};
+
+        self.reader().and_then(|mut conn| {
+            let conn = &mut *conn;
+            let mut ids = BTreeSet::new();
+            ids.extend(
+                killmails::table
+                    .select(killmails::solar_system_id)
+                    .distinct()
+                    .load::<i32>(conn)?,
+            );
+            ids.extend(
+                victims::table
+                    .select(victims::character_id)
+                    .distinct()
+                    .load::<i32>(conn)?,
+            );
+            ids.extend(
+                victims::table
+                    .select(victims::corporation_id)
+                    .distinct()
+                    .load::<i32>(conn)?,
+            );
+            ids.extend(
+                victims::table
+                    .select(victims::alliance_id)
+                    .distinct()
+                    .load::<i32>(conn)?,
+            );
+            ids.extend(
+                victims::table
+                    .select(victims::faction_id)
+                    .distinct()
+                    .load::<i32>(conn)?,
+            );
+            ids.extend(
+                victims::table
+                    .select(victims::ship_type_id)
+                    .distinct()
+                    .load::<i32>(conn)?,
+            );
+            ids.extend(
+                attackers::table
+                    .select(attackers::character_id)
+                    .distinct()
+                    .load::<i32>(conn)?,
+            );
+            ids.extend(
+                attackers::table
+                    .select(attackers::corporation_id)
+                    .distinct()
+                    .load::<i32>(conn)?,
+            );
+            ids.extend(
+                attackers::table
+                    .select(attackers::alliance_id)
+                    .distinct()
+                    .load::<i32>(conn)?,
+            );
+            ids.extend(
+                attackers::table
+                    .select(attackers::faction_id)
+                    .distinct()
+                    .load::<i32>(conn)?,
+            );
+            ids.extend(
+                attackers::table
+                    .select(attackers::ship_type_id)
+                    .distinct()
+                    .load::<i32>(conn)?,
+            );
+            ids.extend(
+                attackers::table
+                    .select(attackers::weapon_type_id)
+                    .distinct()
+                    .load::<i32>(conn)?,
+            );
+            ids.extend(
+                items::table
+                    .select(items::item_type_id)
+                    .distinct()
+                    .load::<i32>(conn)?,
+            );
+            ids.extend(
+                locations::table
+                    .select(locations::constellation_id)
+                    .distinct()
+                    .load::<i32>(conn)?,
+            );
+            ids.extend(
+                locations::table
+                    .select(locations::region_id)
+                    .distinct()
+                    .load::<i32>(conn)?,
+            );
+            ids.remove(&0);
+            Ok(ids.into_iter().collect())
+        })
+    }
+
+    pub fn save_locations(&self, locations: &[models::Location]) -> anyhow::Result<usize> {
+        self.writer().and_then(|mut conn| {
+            conn.transaction::<_, anyhow::Error, _>(|conn| {
+                let mut count = 0;
+                for location in locations {
+                    count += diesel::insert_into(schema::locations::table)
+                        .values(location)
+                        .on_conflict(schema::locations::system_id)
+                        .do_update()
+                        .set(location)
+                        .execute(conn)?;
+                }
+                Ok(count)
             })
+        })
     }
 
-    pub fn cleanup(&mut self, days: u16) -> anyhow::Result<usize> {
-        use diesel::sql_types::Text;
-        use schema::killmails::dsl::*;
+    pub fn unlocated(&self, ids: &[i32]) -> anyhow::Result<Vec<i32>> {
+        use schema::locations::dsl::*;
 
-        let pattern = format!("date('now', '-{days} day')");
-        self.conn
-            .lock()
-            .map_err(|e| anyhow::anyhow!("{e}"))
-            .and_then(|mut conn| {
+        self.reader().and_then(|mut conn| {
+            let mut known = HashSet::new();
+            for chunk in ids.chunks(NAMES_CHUNK) {
+                known.extend(
+                    locations
+                        .filter(system_id.eq_any(chunk))
+                        .select(system_id)
+                        .load::<i32>(&mut *conn)?,
+                );
+            }
+            Ok(ids
+                .iter()
+                .filter(|x| **x != 0 && !known.contains(*x))
+                .copied()
+                .collect())
+        })
+    }
+
+    pub fn save_war_sides(&self, war: &war::War) -> anyhow::Result<usize> {
+        let fetched_at = chrono::Utc::now().timestamp();
+        self.writer().and_then(|mut conn| {
+            conn.transaction::<_, anyhow::Error, _>(|conn| {
                 diesel::delete(
-                    killmails.filter(killmail_time.lt(diesel::dsl::sql::<Text>(&pattern))),
+                    schema::war_sides::table.filter(schema::war_sides::war_id.eq(war.id)),
                 )
-                .execute(&mut *conn)
-                .map_err(|e| anyhow::anyhow!("{e}"))
+                .execute(conn)?;
+                let mut count = 0;
+                for side in models::war_side::WarSide::sides(war, fetched_at) {
+                    count += diesel::insert_into(schema::war_sides::table)
+                        .values(&side)
+                        .on_conflict_do_nothing()
+                        .execute(conn)?;
+                }
+                Ok(count)
             })
+        })
     }
 
-    pub fn remove_dangling_attackers(&mut self) -> anyhow::Result<usize> {
-        use diesel::sql_query;
+    /// Returns the cached (entity, aggressor) sides of the war, or nothing when they are stale
+    pub fn war_sides(
+        &self,
+        id: i32,
+        max_age: chrono::Duration,
+    ) -> anyhow::Result<Vec<(i32, bool)>> {
+        use schema::war_sides::dsl::*;
 
-        self.conn
-            .lock()
-            .map_err(|e| anyhow::anyhow!("{e}"))
-            .and_then(|mut conn| {
-                sql_query("DELETE FROM attackers WHERE killmail_id NOT IN (SELECT killmail_id FROM killmails)")
-                .execute(&mut *conn)
+        let cutoff = since(max_age);
+        self.reader().and_then(|mut conn| {
+            war_sides
+                .filter(war_id.eq(id))
+                .filter(fetched_at.ge(cutoff))
+                .select((entity_id, aggressor))
+                .order(entity_id)
+                .load::<(i32, bool)>(&mut *conn)
+                .map_err(anyhow::Error::from)
+        })
+    }
+
+    pub fn stored_systems(&self) -> anyhow::Result<Vec<i32>> {
+        use schema::killmails::dsl::*;
+
+        self.reader().and_then(|mut conn| {
+            killmails
+                .select(solar_system_id)
+                .distinct()
+                .load::<i32>(&mut *conn)
                 .map_err(|e| anyhow::anyhow!("{e}"))
-            })
+        })
     }
 
-    pub fn remove_dangling_victims(&mut self) -> anyhow::Result<usize> {
-        use diesel::sql_query;
+    pub fn cleanup(&self, days: u16) -> anyhow::Result<usize> {
+        let mut count = 0;
+        for ids in self.expired(days)?.chunks(ARCHIVE_CHUNK) {
+            count += self.remove(ids)?;
+        }
+        Ok(count)
+    }
 
-        self.conn
-            .lock()
-            .map_err(|e| anyhow::anyhow!("{e}"))
-            .and_then(|mut conn| {
-                sql_query("DELETE FROM victims WHERE killmail_id NOT IN (SELECT killmail_id FROM killmails)")
-                .execute(&mut *conn)
+    pub fn expired(&self, days: u16) -> anyhow::Result<Vec<i32>> {
+        use schema::killmails::dsl::*;
+
+        let cutoff = since(chrono::Duration::days(days.into()));
+        self.reader().and_then(|mut conn| {
+            killmails
+                .filter(killmail_timestamp.lt(cutoff))
+                .select(killmail_id)
+                .order(killmail_timestamp)
+                .load::<i32>(&mut *conn)
                 .map_err(|e| anyhow::anyhow!("{e}"))
+        })
+    }
+
+    pub fn archive(&self, archive: &Archive, days: u16) -> anyhow::Result<usize> {
+        let mut count = 0;
+        for ids in self.expired(days)?.chunks(ARCHIVE_CHUNK) {
+            let killmails = ids
+                .iter()
+                .map(|id| self.load(*id))
+                .collect::<anyhow::Result<Vec<killmails::Killmail>>>()?;
+            archive.write(&killmails)?;
+            count += self.remove(ids)?;
+        }
+        Ok(count)
+    }
+
+    pub fn restore(
+        &self,
+        archive: &Archive,
+        date: &chrono::NaiveDate,
+    ) -> anyhow::Result<Vec<(i32, SaveStatus)>> {
+        self.save_batch(&archive.read(date)?)
+    }
+
+    pub fn remove(&self, ids: &[i32]) -> anyhow::Result<usize> {
+        use schema::{attackers, items, killmails, victims, zkb};
+
+        self.writer().and_then(|mut conn| {
+            conn.transaction::<_, anyhow::Error, _>(|conn| {
+                Self::rollups(conn, ids, -1)?.apply(conn)?;
+                rollup::prune(conn)?;
+                diesel::delete(attackers::table.filter(attackers::killmail_id.eq_any(ids)))
+                    .execute(conn)?;
+                diesel::delete(victims::table.filter(victims::killmail_id.eq_any(ids)))
+                    .execute(conn)?;
+                diesel::delete(items::table.filter(items::killmail_id.eq_any(ids)))
+                    .execute(conn)?;
+                diesel::delete(zkb::table.filter(zkb::killmail_id.eq_any(ids))).execute(conn)?;
+                Ok(
+                    diesel::delete(killmails::table.filter(killmails::killmail_id.eq_any(ids)))
+                        .execute(conn)?,
+                )
             })
+        })
     }
 
-    pub fn ids_by_date<S: Into<String>>(&mut self, date: S) -> anyhow::Result<Vec<i32>> {
+    pub fn rebuild_rollups(&self) -> anyhow::Result<usize> {
         use schema::killmails::dsl::*;
 
-        let pattern = format!("{}%", date.into());
-        self.conn
-            .lock()
-            .map_err(|e| anyhow::anyhow!("{e}"))
-            .and_then(|mut conn| {
-                killmails
-                    .filter(killmail_time.like(pattern))
+        self.writer().and_then(|mut conn| {
+            conn.transaction::<_, anyhow::Error, _>(|conn| {
+                rollup::clear(conn)?;
+                let ids = killmails
                     .select(killmail_id)
-                    .load::<i32>(&mut *conn)
-                    .map_err(|e| anyhow::anyhow!("{e}"))
+                    .order(killmail_id)
+                    .load::<i32>(conn)?;
+                for ids in ids.chunks(ARCHIVE_CHUNK) {
+                    Self::rollups(conn, ids, 1)?.apply(conn)?;
+                }
+                Ok(ids.len())
             })
+        })
     }
 
-    pub fn friends(&mut self, rq: SubjectType, rp: ObjectType) -> anyhow::Result<Vec<(i32, i64)>> {
+    fn rollups(conn: &mut DbConnection, ids: &[i32], sign: i32) -> QueryResult<Rollups> {
+        use schema::{attackers, killmails, victims};
+
+        let victims = victims::table
+            .filter(victims::killmail_id.eq_any(ids))
+            .load::<models::victim::Victim>(conn)?
+            .into_iter()
+            .map(|victim| (victim.killmail_id, victim))
+            .collect::<HashMap<i32, models::victim::Victim>>();
+        let mut attackers = HashMap::<i32, Vec<models::attacker::Attacker>>::new();
+        for attacker in attackers::table
+            .filter(attackers::killmail_id.eq_any(ids))
+            .load::<models::attacker::Attacker>(conn)?
+        {
+            attackers
+                .entry(attacker.killmail_id)
+                .or_default()
+                .push(attacker);
+        }
+
+        let mut rollups = Rollups::default();
+        for killmail in killmails::table
+            .filter(killmails::killmail_id.eq_any(ids))
+            .load::<models::killmail::Killmail>(conn)?
+        {
+            let id = killmail.killmail_id;
+            let attackers = attackers.get(&id).map(Vec::as_slice).unwrap_or_default();
+            rollups.add(&killmail, victims.get(&id), attackers, sign);
+        }
+        Ok(rollups)
+    }
+
+    pub fn remove_dangling_attackers(&self) -> anyhow::Result<usize> {
+        use schema::attackers;
+        use schema::killmails;
+
+        self.writer().and_then(|mut conn| {
+            diesel::delete(attackers::table.filter(diesel::dsl::not(
+                attackers::killmail_id.eq_any(killmails::table.select(killmails::killmail_id)),
+            )))
+            .execute(&mut *conn)
+            .map_err(|e| anyhow::anyhow!("{e}"))
+        })
+    }
+
+    pub fn remove_dangling_items(&self) -> anyhow::Result<usize> {
+        use schema::items;
+        use schema::killmails;
+
+        self.writer().and_then(|mut conn| {
+            diesel::delete(items::table.filter(diesel::dsl::not(
+                items::killmail_id.eq_any(killmails::table.select(killmails::killmail_id)),
+            )))
+            .execute(&mut *conn)
+            .map_err(|e| anyhow::anyhow!("{e}"))
+        })
+    }
+
+    pub fn remove_dangling_zkb(&self) -> anyhow::Result<usize> {
+        use schema::killmails;
+        use schema::zkb;
+
+        self.writer().and_then(|mut conn| {
+            diesel::delete(zkb::table.filter(diesel::dsl::not(
+                zkb::killmail_id.eq_any(killmails::table.select(killmails::killmail_id)),
+            )))
+            .execute(&mut *conn)
+            .map_err(|e| anyhow::anyhow!("{e}"))
+        })
+    }
+
+    pub fn remove_dangling_victims(&self) -> anyhow::Result<usize> {
+        use schema::killmails;
+        use schema::victims;
+
+        self.writer().and_then(|mut conn| {
+            diesel::delete(victims::table.filter(diesel::dsl::not(
+                victims::killmail_id.eq_any(killmails::table.select(killmails::killmail_id)),
+            )))
+            .execute(&mut *conn)
+            .map_err(|e| anyhow::anyhow!("{e}"))
+        })
+    }
+
+    pub fn ids_by_date<S: Into<String>>(&self, date: S) -> anyhow::Result<Vec<i32>> {
+        use schema::killmails::dsl::*;
+
+        let pattern = format!("{}%", date.into());
+        self.reader().and_then(|mut conn| {
+            killmails
+                .filter(killmail_time.like(pattern))
+                .select(killmail_id)
+                .load::<i32>(&mut *conn)
+                .map_err(|e| anyhow::anyhow!("{e}"))
+        })
+    }
+
+    pub fn recent_kills(
+        &self,
+        systems: &[i32],
+        hours: u16,
+    ) -> anyhow::Result<Vec<(i32, i32, Option<common::Position>)>> {
+        use schema::killmails;
+        use schema::victims;
+
+        let cutoff = since(chrono::Duration::hours(hours.into()));
+        self.reader().and_then(|mut conn| {
+            let kills = killmails::table
+                .inner_join(victims::table.on(victims::killmail_id.eq(killmails::killmail_id)))
+                .filter(killmails::solar_system_id.eq_any(systems))
+                .filter(killmails::killmail_timestamp.ge(cutoff))
+                .select((
+                    killmails::killmail_id,
+                    killmails::solar_system_id,
+                    victims::position_x,
+                    victims::position_y,
+                    victims::position_z,
+                ))
+                .order(killmails::killmail_timestamp.desc())
+                .load::<(i32, i32, Option<f64>, Option<f64>, Option<f64>)>(&mut *conn)?;
+            Ok(kills
+                .into_iter()
+                .map(|(killmail_id, system_id, x, y, z)| {
+                    let position = match (x, y, z) {
+                        (Some(x), Some(y), Some(z)) => Some(common::Position::new(x, y, z)),
+                        _ => None,
+                    };
+                    (killmail_id, system_id, position)
+                })
+                .collect())
+        })
+    }
+
+    pub fn friends(
+        &self,
+        rq: SubjectType,
+        rp: ObjectType,
+        interval: Interval,
+        page: Page,
+    ) -> anyhow::Result<Paged<(i32, i64)>> {
         use schema::attackers;
         use schema::attackers::dsl::*;
+        use schema::killmails;
 
         let (attacker, assistant) = diesel::alias!(attackers as _1, attackers as _2);
 
-        let attacker_filter: Box<dyn BoxableExpression<_, _, SqlType = diesel::sql_types::Bool>> =
-            match rq {
+        let query = || {
+            let attacker_filter: Filter<_> = match rq {
                 SubjectType::Character(id) => Box::new(attacker.field(character_id).eq(id)),
                 SubjectType::Corporation(id) => Box::new(attacker.field(corporation_id).eq(id)),
                 SubjectType::Alliance(id) => Box::new(attacker.field(alliance_id).eq(id)),
                 SubjectType::Faction(id) => Box::new(attacker.field(faction_id).eq(id)),
             };
-        let assist_filter: Box<dyn BoxableExpression<_, _, SqlType = diesel::sql_types::Bool>> =
-            match rq {
+            let assist_filter: Filter<_> = match rq {
                 SubjectType::Character(id) => Box::new(assistant.field(character_id).ne(id)),
                 SubjectType::Corporation(id) => Box::new(assistant.field(corporation_id).ne(id)),
                 SubjectType::Alliance(id) => Box::new(assistant.field(alliance_id).ne(id)),
                 SubjectType::Faction(id) => Box::new(assistant.field(faction_id).ne(id)),
             };
-        let count = count_star();
+            attacker
+                .inner_join(
+                    assistant.on(attacker.field(killmail_id).eq(assistant.field(killmail_id))),
+                )
+                .inner_join(
+                    killmails::table.on(killmails::killmail_id.eq(attacker.field(killmail_id))),
+                )
+                .filter(attacker_filter)
+                .filter(assist_filter)
+                .filter(assistant.field(character_id).ne(0))
+                .filter(killmails::killmail_timestamp.ge(interval.start()))
+                .filter(killmails::killmail_timestamp.lt(interval.end()))
+        };
 
-        self.conn
-            .lock()
-            .map_err(|e| anyhow::anyhow!("{e}"))
-            .and_then(|mut conn| {
-                match rp {
-                    ObjectType::Character => attacker
-                        .inner_join(
-                            assistant
-                                .on(attacker.field(killmail_id).eq(assistant.field(killmail_id))),
-                        )
-                        .filter(attacker_filter)
-                        .filter(assist_filter)
-                        .filter(assistant.field(character_id).ne(0))
-                        .group_by(assistant.field(character_id))
-                        .select((assistant.field(character_id), count))
-                        .order(count.desc())
-                        .load::<(i32, i64)>(&mut *conn),
-                    ObjectType::Corporation => attacker
-                        .inner_join(
-                            assistant
-                                .on(attacker.field(killmail_id).eq(assistant.field(killmail_id))),
-                        )
-                        .filter(attacker_filter)
-                        .filter(assist_filter)
-                        .filter(assistant.field(character_id).ne(0))
-                        .group_by(assistant.field(corporation_id))
-                        .select((assistant.field(corporation_id), count))
-                        .order(count.desc())
-                        .load::<(i32, i64)>(&mut *conn),
-                    ObjectType::Alliance => attacker
-                        .inner_join(
-                            assistant
-                                .on(attacker.field(killmail_id).eq(assistant.field(killmail_id))),
-                        )
-                        .filter(attacker_filter)
-                        .filter(assist_filter)
-                        .filter(assistant.field(character_id).ne(0))
-                        .group_by(assistant.field(alliance_id))
-                        .select((assistant.field(alliance_id), count))
-                        .order(count.desc())
-                        .load::<(i32, i64)>(&mut *conn),
-                    ObjectType::Faction => attacker
-                        .inner_join(
-                            assistant
-                                .on(attacker.field(killmail_id).eq(assistant.field(killmail_id))),
-                        )
-                        .filter(attacker_filter)
-                        .filter(assist_filter)
-                        .filter(assistant.field(character_id).ne(0))
-                        .group_by(assistant.field(faction_id))
-                        .select((assistant.field(faction_id), count))
-                        .order(count.desc())
-                        .load::<(i32, i64)>(&mut *conn),
+        let count = count_star();
+        self.reader().and_then(|mut conn| {
+            let conn = &mut *conn;
+            Ok(match rp {
+                ObjectType::Character => {
+                    paged_groups!(conn, query, assistant.field(character_id), count, page)
+                }
+                ObjectType::Corporation => {
+                    paged_groups!(conn, query, assistant.field(corporation_id), count, page)
+                }
+                ObjectType::Alliance => {
+                    paged_groups!(conn, query, assistant.field(alliance_id), count, page)
+                }
+                ObjectType::Faction => {
+                    paged_groups!(conn, query, assistant.field(faction_id), count, page)
                 }
-                .map_err(|e| anyhow::anyhow!("{e}"))
             })
+        })
     }
 
-    pub fn enemies(&mut self, rq: SubjectType, rp: ObjectType) -> anyhow::Result<Vec<(i32, i64)>> {
-        use schema::attackers;
-        use schema::attackers::dsl::*;
-        use schema::victims;
-        use schema::victims::dsl::*;
+    pub fn enemies(
+        &self,
+        rq: SubjectType,
+        rp: ObjectType,
+        interval: Interval,
+        page: Page,
+    ) -> anyhow::Result<Paged<(i32, i64)>> {
+        use schema::{attackers, killmails, victims};
 
-        let victim: Box<dyn BoxableExpression<_, _, SqlType = diesel::sql_types::Bool>> = match rq {
-            SubjectType::Character(id) => Box::new(victims::character_id.eq(id)),
-            SubjectType::Corporation(id) => Box::new(victims::corporation_id.eq(id)),
-            SubjectType::Alliance(id) => Box::new(victims::alliance_id.eq(id)),
-            SubjectType::Faction(id) => Box::new(victims::faction_id.eq(id)),
+        let query = || {
+            attackers::table
+                .inner_join(victims::table.on(attackers::killmail_id.eq(victims::killmail_id)))
+                .inner_join(killmails::table.on(killmails::killmail_id.eq(attackers::killmail_id)))
+                .filter(victim_filter(rq))
+                .filter(attackers::character_id.ne(0))
+                .filter(killmails::killmail_timestamp.ge(interval.start()))
+                .filter(killmails::killmail_timestamp.lt(interval.end()))
         };
 
-        let count = count_star();
-        self.conn
-            .lock()
-            .map_err(|e| anyhow::anyhow!("{e}"))
-            .and_then(|mut conn| {
-                match rp {
-                    ObjectType::Character => attackers
-                        .inner_join(victims.on(attackers::killmail_id.eq(victims::killmail_id)))
-                        .filter(victim)
-                        .filter(attackers::character_id.ne(0))
-                        .group_by(attackers::character_id)
-                        .select((attackers::character_id, count))
-                        .order(count.desc())
-                        .load::<(i32, i64)>(&mut *conn),
-                    ObjectType::Corporation => attackers
-                        .inner_join(victims.on(attackers::killmail_id.eq(victims::killmail_id)))
-                        .filter(victim)
-                        .filter(attackers::character_id.ne(0))
-                        .group_by(attackers::corporation_id)
-                        .select((attackers::corporation_id, count))
-                        .order(count.desc())
-                        .load::<(i32, i64)>(&mut *conn),
-                    ObjectType::Alliance => attackers
-                        .inner_join(victims.on(attackers::killmail_id.eq(victims::killmail_id)))
-                        .filter(victim)
-                        .filter(attackers::character_id.ne(0))
-                        .group_by(attackers::alliance_id)
-                        .select((attackers::alliance_id, count))
-                        .order(count.desc())
-                        .load::<(i32, i64)>(&mut *conn),
-                    ObjectType::Faction => attackers
-                        .inner_join(victims.on(attackers::killmail_id.eq(victims::killmail_id)))
-                        .filter(victim)
-                        .filter(attackers::character_id.ne(0))
-                        .group_by(attackers::faction_id)
-                        .select((attackers::faction_id, count))
-                        .order(count.desc())
-                        .load::<(i32, i64)>(&mut *conn),
+        self.reader().and_then(|mut conn| {
+            let conn = &mut *conn;
+            Ok(match rp {
+                ObjectType::Character => {
+                    paged_groups!(conn, query, attackers::character_id, count_star(), page)
+                }
+                ObjectType::Corporation => {
+                    paged_groups!(conn, query, attackers::corporation_id, count_star(), page)
+                }
+                ObjectType::Alliance => {
+                    paged_groups!(conn, query, attackers::alliance_id, count_star(), page)
+                }
+                ObjectType::Faction => {
+                    paged_groups!(conn, query, attackers::faction_id, count_star(), page)
                 }
-                .map_err(|e| anyhow::anyhow!("{e}"))
             })
+        })
     }
 
-    pub fn wins(&mut self, rq: SubjectType) -> anyhow::Result<(i64, Option<i64>)> {
+    pub fn wins(&self, rq: SubjectType, interval: Interval) -> anyhow::Result<(i64, Option<i64>)> {
         use diesel::dsl::{count, sum};
         use schema::attackers;
+        use schema::killmails;
 
-        let filter: Box<dyn BoxableExpression<_, _, SqlType = diesel::sql_types::Bool>> = match rq {
-            SubjectType::Character(id) => Box::new(attackers::character_id.eq(id)),
-            SubjectType::Corporation(id) => Box::new(attackers::corporation_id.eq(id)),
-            SubjectType::Alliance(id) => Box::new(attackers::alliance_id.eq(id)),
-            SubjectType::Faction(id) => Box::new(attackers::faction_id.eq(id)),
-        };
+        if let Some((start, end)) = interval.whole_days() {
+            return self.reader().and_then(|mut conn| {
+                rollup::wins(&mut conn, rq.entity(), start, end).map_err(|e| anyhow::anyhow!("{e}"))
+            });
+        }
 
-        self.conn
-            .lock()
-            .map_err(|e| anyhow::anyhow!("{e}"))
-            .and_then(|mut conn| {
-                attackers::table
-                    .filter(filter)
-                    .select((count(attackers::killmail_id), sum(attackers::damage_done)))
-                    .first::<(i64, Option<i64>)>(&mut *conn)
-                    .map_err(|e| anyhow::anyhow!("{e}"))
-            })
+        self.reader().and_then(|mut conn| {
+            attackers::table
+                .inner_join(killmails::table.on(killmails::killmail_id.eq(attackers::killmail_id)))
+                .filter(attacker_filter(rq))
+                .filter(killmails::killmail_timestamp.ge(interval.start()))
+                .filter(killmails::killmail_timestamp.lt(interval.end()))
+                .select((count(attackers::killmail_id), sum(attackers::damage_done)))
+                .first::<(i64, Option<i64>)>(&mut *conn)
+                .map_err(|e| anyhow::anyhow!("{e}"))
+        })
     }
 
-    pub fn wins_ships(&mut self, rq: SubjectType) -> anyhow::Result<Vec<(i32, i64)>> {
+    pub fn wins_ships(
+        &self,
+        rq: SubjectType,
+        interval: Interval,
+        page: Page,
+    ) -> anyhow::Result<Paged<(i32, i64)>> {
         use diesel::dsl::count;
         use schema::attackers;
+        use schema::killmails;
 
-        let filter: Box<dyn BoxableExpression<_, _, SqlType = diesel::sql_types::Bool>> = match rq {
-            SubjectType::Character(id) => Box::new(attackers::character_id.eq(id)),
-            SubjectType::Corporation(id) => Box::new(attackers::corporation_id.eq(id)),
-            SubjectType::Alliance(id) => Box::new(attackers::alliance_id.eq(id)),
-            SubjectType::Faction(id) => Box::new(attackers::faction_id.eq(id)),
+        if let Some((start, end)) = interval.whole_days() {
+            return self.reader().and_then(|mut conn| {
+                rollup::wins_ships(&mut conn, rq.entity(), start, end, page)
+                    .map_err(|e| anyhow::anyhow!("{e}"))
+            });
+        }
+
+        let query = || {
+            attackers::table
+                .inner_join(killmails::table.on(killmails::killmail_id.eq(attackers::killmail_id)))
+                .filter(attacker_filter(rq))
+                .filter(killmails::killmail_timestamp.ge(interval.start()))
+                .filter(killmails::killmail_timestamp.lt(interval.end()))
         };
 
-        self.conn
-            .lock()
-            .map_err(|e| anyhow::anyhow!("{e}"))
-            .and_then(|mut conn| {
-                attackers::table
-                    .filter(filter)
-                    .group_by(attackers::ship_type_id)
-                    .select((attackers::ship_type_id, count(attackers::ship_type_id)))
-                    .order(count(attackers::ship_type_id).desc())
-                    .load::<(i32, i64)>(&mut *conn)
-                    .map_err(|e| anyhow::anyhow!("{e}"))
-            })
+        self.reader().and_then(|mut conn| {
+            Ok(paged_groups!(
+                &mut *conn,
+                query,
+                attackers::ship_type_id,
+                count(attackers::ship_type_id),
+                page
+            ))
+        })
     }
 
-    pub fn wins_systems(&mut self, rq: SubjectType) -> anyhow::Result<Vec<(i32, i64)>> {
+    pub fn wins_systems(
+        &self,
+        rq: SubjectType,
+        interval: Interval,
+        page: Page,
+    ) -> anyhow::Result<Paged<(i32, i64)>> {
         use diesel::dsl::count;
         use schema::attackers;
         use schema::killmails;
-        use schema::killmails::dsl::*;
 
-        let filter: Box<dyn BoxableExpression<_, _, SqlType = diesel::sql_types::Bool>> = match rq {
-            SubjectType::Character(id) => Box::new(attackers::character_id.eq(id)),
-            SubjectType::Corporation(id) => Box::new(attackers::corporation_id.eq(id)),
-            SubjectType::Alliance(id) => Box::new(attackers::alliance_id.eq(id)),
-            SubjectType::Faction(id) => Box::new(attackers::faction_id.eq(id)),
+        if let Some((start, end)) = interval.whole_days() {
+            return self.reader().and_then(|mut conn| {
+                rollup::wins_systems(&mut conn, rq.entity(), start, end, page)
+                    .map_err(|e| anyhow::anyhow!("{e}"))
+            });
+        }
+
+        let query = || {
+            attackers::table
+                .inner_join(killmails::table.on(killmails::killmail_id.eq(attackers::killmail_id)))
+                .filter(attacker_filter(rq))
+                .filter(killmails::killmail_timestamp.ge(interval.start()))
+                .filter(killmails::killmail_timestamp.lt(interval.end()))
         };
 
-        self.conn
-            .lock()
-            .map_err(|e| anyhow::anyhow!("{e}"))
-            .and_then(|mut conn| {
-                attackers::table
-                    .filter(filter)
-                    .inner_join(killmails.on(killmails::killmail_id.eq(attackers::killmail_id)))
-                    .group_by(killmails::solar_system_id)
-                    .select((
-                        killmails::solar_system_id,
-                        count(killmails::solar_system_id),
-                    ))
-                    .order(count(killmails::solar_system_id).desc())
-                    .load::<(i32, i64)>(&mut *conn)
-                    .map_err(|e| anyhow::anyhow!("{e}"))
-            })
+        self.reader().and_then(|mut conn| {
+            Ok(paged_groups!(
+                &mut *conn,
+                query,
+                killmails::solar_system_id,
+                count(killmails::solar_system_id),
+                page
+            ))
+        })
     }
 
-    pub fn losses(&mut self, rq: SubjectType) -> anyhow::Result<(i64, Option<i64>)> {
+    pub fn losses(
+        &self,
+        rq: SubjectType,
+        interval: Interval,
+    ) -> anyhow::Result<(i64, Option<i64>)> {
         use diesel::dsl::{count, sum};
+        use schema::killmails;
         use schema::victims;
 
-        let filter: Box<dyn BoxableExpression<_, _, SqlType = diesel::sql_types::Bool>> = match rq {
-            SubjectType::Character(id) => Box::new(victims::character_id.eq(id)),
-            SubjectType::Corporation(id) => Box::new(victims::corporation_id.eq(id)),
-            SubjectType::Alliance(id) => Box::new(victims::alliance_id.eq(id)),
-            SubjectType::Faction(id) => Box::new(victims::faction_id.eq(id)),
-        };
-
-        self.conn
-            .lock()
-            .map_err(|e| anyhow::anyhow!("{e}"))
-            .and_then(|mut conn| {
-                victims::table
-                    .filter(filter)
-                    .select((count(victims::killmail_id), sum(victims::damage_taken)))
-                    .first::<(i64, Option<i64>)>(&mut *conn)
+        if let Some((start, end)) = interval.whole_days() {
+            return self.reader().and_then(|mut conn| {
+                rollup::losses(&mut conn, rq.entity(), start, end)
                     .map_err(|e| anyhow::anyhow!("{e}"))
-            })
+            });
+        }
+
+        self.reader().and_then(|mut conn| {
+            victims::table
+                .inner_join(killmails::table.on(killmails::killmail_id.eq(victims::killmail_id)))
+                .filter(victim_filter(rq))
+                .filter(killmails::killmail_timestamp.ge(interval.start()))
+                .filter(killmails::killmail_timestamp.lt(interval.end()))
+                .select((count(victims::killmail_id), sum(victims::damage_taken)))
+                .first::<(i64, Option<i64>)>(&mut *conn)
+                .map_err(|e| anyhow::anyhow!("{e}"))
+        })
     }
 
-    pub fn losses_ships(&mut self, rq: SubjectType) -> anyhow::Result<Vec<(i32, i64)>> {
+    pub fn losses_ships(
+        &self,
+        rq: SubjectType,
+        interval: Interval,
+        page: Page,
+    ) -> anyhow::Result<Paged<(i32, i64)>> {
         use diesel::dsl::count;
+        use schema::killmails;
         use schema::victims;
 
-        let filter: Box<dyn BoxableExpression<_, _, SqlType = diesel::sql_types::Bool>> = match rq {
-            SubjectType::Character(id) => Box::new(victims::character_id.eq(id)),
-            SubjectType::Corporation(id) => Box::new(victims::corporation_id.eq(id)),
-            SubjectType::Alliance(id) => Box::new(victims::alliance_id.eq(id)),
-            SubjectType::Faction(id) => Box::new(victims::faction_id.eq(id)),
+        if let Some((start, end)) = interval.whole_days() {
+            return self.reader().and_then(|mut conn| {
+                rollup::losses_ships(&mut conn, rq.entity(), start, end, page)
+                    .map_err(|e| anyhow::anyhow!("{e}"))
+            });
+        }
+
+        let query = || {
+            victims::table
+                .inner_join(killmails::table.on(killmails::killmail_id.eq(victims::killmail_id)))
+                .filter(victim_filter(rq))
+                .filter(killmails::killmail_timestamp.ge(interval.start()))
+                .filter(killmails::killmail_timestamp.lt(interval.end()))
         };
 
-        self.conn
-            .lock()
-            .map_err(|e| anyhow::anyhow!("{e}"))
-            .and_then(|mut conn| {
-                victims::table
-                    .filter(filter)
-                    .group_by(victims::ship_type_id)
-                    .select((victims::ship_type_id, count(victims::ship_type_id)))
-                    .order(count(victims::ship_type_id).desc())
-                    .load::<(i32, i64)>(&mut *conn)
-                    .map_err(|e| anyhow::anyhow!("{e}"))
-            })
+        self.reader().and_then(|mut conn| {
+            Ok(paged_groups!(
+                &mut *conn,
+                query,
+                victims::ship_type_id,
+                count(victims::ship_type_id),
+                page
+            ))
+        })
     }
 
-    pub fn losses_systems(&mut self, rq: SubjectType) -> anyhow::Result<Vec<(i32, i64)>> {
+    pub fn losses_systems(
+        &self,
+        rq: SubjectType,
+        interval: Interval,
+        page: Page,
+    ) -> anyhow::Result<Paged<(i32, i64)>> {
         use diesel::dsl::count;
         use schema::killmails;
-        use schema::killmails::dsl::*;
         use schema::victims;
 
-        let filter: Box<dyn BoxableExpression<_, _, SqlType = diesel::sql_types::Bool>> = match rq {
-            SubjectType::Character(id) => Box::new(victims::character_id.eq(id)),
-            SubjectType::Corporation(id) => Box::new(victims::corporation_id.eq(id)),
-            SubjectType::Alliance(id) => Box::new(victims::alliance_id.eq(id)),
-            SubjectType::Faction(id) => Box::new(victims::faction_id.eq(id)),
+        if let Some((start, end)) = interval.whole_days() {
+            return self.reader().and_then(|mut conn| {
+                rollup::losses_systems(&mut conn, rq.entity(), start, end, page)
+                    .map_err(|e| anyhow::anyhow!("{e}"))
+            });
+        }
+
+        let query = || {
+            victims::table
+                .inner_join(killmails::table.on(killmails::killmail_id.eq(victims::killmail_id)))
+                .filter(victim_filter(rq))
+                .filter(killmails::killmail_timestamp.ge(interval.start()))
+                .filter(killmails::killmail_timestamp.lt(interval.end()))
         };
 
-        self.conn
-            .lock()
-            .map_err(|e| anyhow::anyhow!("{e}"))
-            .and_then(|mut conn| {
-                victims::table
-                    .filter(filter)
-                    .inner_join(killmails.on(killmails::killmail_id.eq(victims::killmail_id)))
-                    .group_by(killmails::solar_system_id)
-                    .select((
-                        killmails::solar_system_id,
-                        count(killmails::solar_system_id),
-                    ))
-                    .order(count(killmails::solar_system_id).desc())
-                    .load::<(i32, i64)>(&mut *conn)
-                    .map_err(|e| anyhow::anyhow!("{e}"))
+        self.reader().and_then(|mut conn| {
+            Ok(paged_groups!(
+                &mut *conn,
+                query,
+                killmails::solar_system_id,
+                count(killmails::solar_system_id),
+                page
+            ))
+        })
+    }
+
+    pub fn hotspots(
+        &self,
+        area: Area,
+        interval: Interval,
+        page: Page,
+    ) -> anyhow::Result<Paged<(i32, i64)>> {
+        use diesel::dsl::count;
+        use schema::{killmails, locations};
+
+        let located = || {
+            killmails::table
+                .inner_join(
+                    locations::table.on(locations::system_id.eq(killmails::solar_system_id)),
+                )
+                .filter(killmails::killmail_timestamp.ge(interval.start()))
+                .filter(killmails::killmail_timestamp.lt(interval.end()))
+        };
+        let query = || {
+            killmails::table
+                .filter(killmails::killmail_timestamp.ge(interval.start()))
+                .filter(killmails::killmail_timestamp.lt(interval.end()))
+        };
+
+        self.reader().and_then(|mut conn| {
+            let conn = &mut *conn;
+            let count = count(killmails::killmail_id);
+            Ok(match area {
+                Area::System => {
+                    paged_groups!(conn, query, killmails::solar_system_id, count, page)
+                }
+                Area::Constellation => {
+                    paged_groups!(conn, located, locations::constellation_id, count, page)
+                }
+                Area::Region => paged_groups!(conn, located, locations::region_id, count, page),
             })
+        })
+    }
+
+    pub fn ship_killers(
+        &self,
+        ship: i32,
+        interval: Interval,
+        page: Page,
+    ) -> anyhow::Result<Paged<(i32, i64)>> {
+        use diesel::dsl::count;
+        use schema::{attackers, killmails, victims};
+
+        let query = || {
+            attackers::table
+                .inner_join(killmails::table.on(killmails::killmail_id.eq(attackers::killmail_id)))
+                .inner_join(victims::table.on(victims::killmail_id.eq(attackers::killmail_id)))
+                .filter(victims::ship_type_id.eq(ship))
+                .filter(attackers::ship_type_id.ne(0))
+                .filter(killmails::killmail_timestamp.ge(interval.start()))
+                .filter(killmails::killmail_timestamp.lt(interval.end()))
+        };
+
+        self.reader().and_then(|mut conn| {
+            Ok(paged_groups!(
+                &mut *conn,
+                query,
+                attackers::ship_type_id,
+                count(attackers::ship_type_id),
+                page
+            ))
+        })
+    }
+
+    pub fn weapons(
+        &self,
+        rq: SubjectType,
+        interval: Interval,
+        page: Page,
+    ) -> anyhow::Result<Paged<(i32, i64)>> {
+        use diesel::dsl::count;
+        use schema::{attackers, killmails};
+
+        let query = || {
+            attackers::table
+                .inner_join(killmails::table.on(killmails::killmail_id.eq(attackers::killmail_id)))
+                .filter(attacker_filter(rq))
+                .filter(attackers::weapon_type_id.ne(0))
+                .filter(killmails::killmail_timestamp.ge(interval.start()))
+                .filter(killmails::killmail_timestamp.lt(interval.end()))
+        };
+
+        self.reader().and_then(|mut conn| {
+            Ok(paged_groups!(
+                &mut *conn,
+                query,
+                attackers::weapon_type_id,
+                count(attackers::weapon_type_id),
+                page
+            ))
+        })
+    }
+
+    pub fn ships_against(
+        &self,
+        rq: SubjectType,
+        target: SubjectType,
+        interval: Interval,
+        page: Page,
+    ) -> anyhow::Result<Paged<(i32, i64)>> {
+        use diesel::dsl::count;
+        use schema::{attackers, killmails, victims};
+
+        let query = || {
+            attackers::table
+                .inner_join(killmails::table.on(killmails::killmail_id.eq(attackers::killmail_id)))
+                .inner_join(victims::table.on(victims::killmail_id.eq(attackers::killmail_id)))
+                .filter(attacker_filter(rq))
+                .filter(victim_filter(target))
+                .filter(attackers::ship_type_id.ne(0))
+                .filter(killmails::killmail_timestamp.ge(interval.start()))
+                .filter(killmails::killmail_timestamp.lt(interval.end()))
+        };
+
+        self.reader().and_then(|mut conn| {
+            Ok(paged_groups!(
+                &mut *conn,
+                query,
+                attackers::ship_type_id,
+                count(attackers::ship_type_id),
+                page
+            ))
+        })
     }
 
     pub fn lost_ships(
-        &mut self,
+        &self,
         rq: SubjectType,
         ship_id: i32,
-    ) -> anyhow::Result<Vec<(i32, i32, i32, i32, i32, i32, i32, String)>> {
+        interval: Interval,
+        page: Page,
+    ) -> anyhow::Result<Paged<(i32, i32, i32, i32, i32, i32, i32, String)>> {
         use schema::killmails;
         use schema::killmails::dsl::*;
         use schema::victims;
 
-        let filter: Box<dyn BoxableExpression<_, _, SqlType = diesel::sql_types::Bool>> = match rq {
-            SubjectType::Character(id) => Box::new(victims::character_id.eq(id)),
-            SubjectType::Corporation(id) => Box::new(victims::corporation_id.eq(id)),
-            SubjectType::Alliance(id) => Box::new(victims::alliance_id.eq(id)),
-            SubjectType::Faction(id) => Box::new(victims::faction_id.eq(id)),
+        let query = || {
+            victims::table
+                .inner_join(killmails.on(killmails::killmail_id.eq(victims::killmail_id)))
+                .filter(victim_filter(rq))
+                .filter(victims::ship_type_id.eq(ship_id))
+                .filter(killmails::killmail_timestamp.ge(interval.start()))
+                .filter(killmails::killmail_timestamp.lt(interval.end()))
         };
 
-        self.conn
-            .lock()
-            .map_err(|e| anyhow::anyhow!("{e}"))
-            .and_then(|mut conn| {
-                victims::table
-                    .inner_join(killmails.on(killmails::killmail_id.eq(victims::killmail_id)))
-                    .filter(filter)
-                    .filter(victims::ship_type_id.eq(ship_id))
-                    .select((
-                        killmails::killmail_id,
-                        victims::character_id,
-                        victims::corporation_id,
-                        victims::alliance_id,
-                        victims::ship_type_id,
-                        victims::damage_taken,
-                        killmails::solar_system_id,
-                        killmails::killmail_time,
-                    ))
-                    .order(killmails::killmail_time.desc())
-                    .load::<(i32, i32, i32, i32, i32, i32, i32, String)>(&mut *conn)
-                    .map_err(|e| anyhow::anyhow!("{e}"))
-            })
+        self.reader().and_then(|mut conn| {
+            let total = query().count().get_result::<i64>(&mut *conn)?;
+            let query = query()
+                .select((
+                    killmails::killmail_id,
+                    victims::character_id,
+                    victims::corporation_id,
+                    victims::alliance_id,
+                    victims::ship_type_id,
+                    victims::damage_taken,
+                    killmails::solar_system_id,
+                    killmails::killmail_time,
+                ))
+                .into_boxed();
+            let query = match page.order {
+                Order::Desc => query.order((
+                    killmails::killmail_timestamp.desc(),
+                    killmails::killmail_id.desc(),
+                )),
+                Order::Asc => query.order((
+                    killmails::killmail_timestamp.asc(),
+                    killmails::killmail_id.asc(),
+                )),
+            };
+            let rows = query
+                .limit(page.limit.unwrap_or(i64::MAX))
+                .offset(page.offset)
+                .load::<(i32, i32, i32, i32, i32, i32, i32, String)>(&mut *conn)?;
+            Ok(Paged { rows, total })
+        })
     }
 
     pub fn lost_in_system(
-        &mut self,
+        &self,
         rq: SubjectType,
         system_id: i32,
-    ) -> anyhow::Result<Vec<(i32, i32, i32, i32, i32, i32, i32, String)>> {
+        interval: Interval,
+        page: Page,
+    ) -> anyhow::Result<Paged<(i32, i32, i32, i32, i32, i32, i32, String)>> {
         use schema::killmails;
         use schema::killmails::dsl::*;
         use schema::victims;
 
-        let filter: Box<dyn BoxableExpression<_, _, SqlType = diesel::sql_types::Bool>> = match rq {
-            SubjectType::Character(id) => Box::new(victims::character_id.eq(id)),
-            SubjectType::Corporation(id) => Box::new(victims::corporation_id.eq(id)),
-            SubjectType::Alliance(id) => Box::new(victims::alliance_id.eq(id)),
-            SubjectType::Faction(id) => Box::new(victims::faction_id.eq(id)),
+        let query = || {
+            victims::table
+                .inner_join(killmails.on(killmails::killmail_id.eq(victims::killmail_id)))
+                .filter(victim_filter(rq))
+                .filter(killmails::solar_system_id.eq(system_id))
+                .filter(killmails::killmail_timestamp.ge(interval.start()))
+                .filter(killmails::killmail_timestamp.lt(interval.end()))
         };
 
-        self.conn
-            .lock()
-            .map_err(|e| anyhow::anyhow!("{e}"))
-            .and_then(|mut conn| {
-                victims::table
-                    .inner_join(killmails.on(killmails::killmail_id.eq(victims::killmail_id)))
-                    .filter(filter)
-                    .filter(killmails::solar_system_id.eq(system_id))
-                    .select((
-                        killmails::killmail_id,
-                        victims::character_id,
-                        victims::corporation_id,
-                        victims::alliance_id,
-                        victims::ship_type_id,
-                        victims::damage_taken,
-                        killmails::solar_system_id,
-                        killmails::killmail_time,
-                    ))
-                    .order(killmails::killmail_time.desc())
-                    .load::<(i32, i32, i32, i32, i32, i32, i32, String)>(&mut *conn)
-                    .map_err(|e| anyhow::anyhow!("{e}"))
-            })
+        self.reader().and_then(|mut conn| {
+            let total = query().count().get_result::<i64>(&mut *conn)?;
+            let query = query()
+                .select((
+                    killmails::killmail_id,
+                    victims::character_id,
+                    victims::corporation_id,
+                    victims::alliance_id,
+                    victims::ship_type_id,
+                    victims::damage_taken,
+                    killmails::solar_system_id,
+                    killmails::killmail_time,
+                ))
+                .into_boxed();
+            let query = match page.order {
+                Order::Desc => query.order((
+                    killmails::killmail_timestamp.desc(),
+                    killmails::killmail_id.desc(),
+                )),
+                Order::Asc => query.order((
+                    killmails::killmail_timestamp.asc(),
+                    killmails::killmail_id.asc(),
+                )),
+            };
+            let rows = query
+                .limit(page.limit.unwrap_or(i64::MAX))
+                .offset(page.offset)
+                .load::<(i32, i32, i32, i32, i32, i32, i32, String)>(&mut *conn)?;
+            Ok(Paged { rows, total })
+        })
     }
 
-    pub fn activity(&mut self, rq: SubjectType) -> anyhow::Result<HashMap<u32, i32>> {
-        use schema::attackers;
-        use schema::killmails;
+    pub fn gang(&self, rq: SubjectType, interval: Interval) -> anyhow::Result<Gang> {
+        use schema::{attackers, killmails};
 
-        let attacker_filter: Box<dyn BoxableExpression<_, _, SqlType = diesel::sql_types::Bool>> =
-            match rq {
-                SubjectType::Character(id) => Box::new(attackers::character_id.eq(id)),
-                SubjectType::Corporation(id) => Box::new(attackers::corporation_id.eq(id)),
-                SubjectType::Alliance(id) => Box::new(attackers::alliance_id.eq(id)),
-                SubjectType::Faction(id) => Box::new(attackers::faction_id.eq(id)),
-            };
+        let gang = diesel::alias!(attackers as gang);
 
-        let dates = self
-            .conn
-            .lock()
-            .map_err(|e| anyhow::anyhow!("{e}"))
-            .and_then(|mut conn| {
-                killmails::table
-                    .inner_join(
-                        attackers::table.on(killmails::killmail_id.eq(attackers::killmail_id)),
-                    )
-                    .filter(attacker_filter)
-                    .select(killmails::killmail_time)
-                    .load::<String>(&mut *conn)
-                    .map_err(|e| anyhow::anyhow!("{e}"))
-            })?;
+        let (mut sizes, members) = self.reader().and_then(|mut conn| {
+            let sizes = gang
+                .inner_join(
+                    killmails::table
+                        .on(killmails::killmail_id.eq(gang.field(attackers::killmail_id))),
+                )
+                .filter(
+                    gang.field(attackers::killmail_id).eq_any(
+                        attackers::table
+                            .filter(attacker_filter(rq))
+                            .select(attackers::killmail_id),
+                    ),
+                )
+                .filter(killmails::killmail_timestamp.ge(interval.start()))
+                .filter(killmails::killmail_timestamp.lt(interval.end()))
+                .group_by(gang.field(attackers::killmail_id))
+                .select(count_star())
+                .load::<i64>(&mut *conn)?;
+            let members = attackers::table
+                .inner_join(killmails::table.on(killmails::killmail_id.eq(attackers::killmail_id)))
+                .filter(attacker_filter(rq))
+                .filter(killmails::killmail_timestamp.ge(interval.start()))
+                .filter(killmails::killmail_timestamp.lt(interval.end()))
+                .select(count_star())
+                .first::<i64>(&mut *conn)?;
+            Ok((sizes, members))
+        })?;
+
+        if sizes.is_empty() {
+            return Ok(Gang::default());
+        }
+        sizes.sort_unstable();
+        let kills = sizes.len();
+        let median = if kills % 2 == 0 {
+            (sizes[kills / 2 - 1] + sizes[kills / 2]) as f64 / 2.0
+        } else {
+            sizes[kills / 2] as f64
+        };
+        let mut distribution = BTreeMap::new();
+        for size in &sizes {
+            *distribution.entry(*size).or_insert(0) += 1;
+        }
+
+        Ok(Gang {
+            kills: kills as i64,
+            solo: distribution.get(&1).copied().unwrap_or_default() as f64 / kills as f64,
+            sizes: distribution.into_iter().collect(),
+            median,
+            members: members as f64 / kills as f64,
+        })
+    }
+
+    pub fn activity(
+        &self,
+        rq: SubjectType,
+        interval: Interval,
+        tz: FixedOffset,
+    ) -> anyhow::Result<Activity> {
+        use diesel::dsl::{count, sql};
+        use diesel::sql_types::BigInt;
+        use schema::{attackers, killmails, victims};
+
+        // hour of the week, monday 00:00 local time being 0 (the epoch was a thursday)
+        let week = format!(
+            "((killmail_timestamp + {}) / 3600 + 72) % 168",
+            tz.local_minus_utc()
+        );
+        let hour = || sql::<BigInt>(&week);
+
+        let (wins, losses) = self.reader().and_then(|mut conn| {
+            let wins = killmails::table
+                .inner_join(attackers::table.on(killmails::killmail_id.eq(attackers::killmail_id)))
+                .filter(attacker_filter(rq))
+                .filter(killmails::killmail_timestamp.ge(interval.start()))
+                .filter(killmails::killmail_timestamp.lt(interval.end()))
+                .group_by(hour())
+                .select((hour(), count(attackers::killmail_id).aggregate_distinct()))
+                .load::<(i64, i64)>(&mut *conn)?;
+            let losses = killmails::table
+                .inner_join(victims::table.on(killmails::killmail_id.eq(victims::killmail_id)))
+                .filter(victim_filter(rq))
+                .filter(killmails::killmail_timestamp.ge(interval.start()))
+                .filter(killmails::killmail_timestamp.lt(interval.end()))
+                .group_by(hour())
+                .select((hour(), count_star()))
+                .load::<(i64, i64)>(&mut *conn)?;
+            Ok((wins, losses))
+        })?;
+
+        let mut activity = Activity::default();
+        for (matrix, rows) in [(&mut activity.wins, wins), (&mut activity.losses, losses)] {
+            for (hour, count) in rows {
+                let (day, hour) = (hour.div_euclid(24), hour.rem_euclid(24));
+                if let Some(cell) = usize::try_from(day)
+                    .ok()
+                    .and_then(|day| matrix.get_mut(day))
+                    .and_then(|row| row.get_mut(hour as usize))
+                {
+                    *cell += count;
+                }
+            }
+        }
+
+        Ok(activity)
+    }
+
+    pub fn versus(
+        &self,
+        a: SubjectType,
+        b: SubjectType,
+        interval: Interval,
+    ) -> anyhow::Result<Versus> {
+        let (kills_a, damage_a) = self.clash(a, b, interval)?;
+        let (kills_b, damage_b) = self.clash(b, a, interval)?;
+
+        let ranked = |counts: BTreeMap<i32, i64>| {
+            let mut rows = counts.into_iter().collect::<Vec<_>>();
+            rows.sort_by(|x, y| y.1.cmp(&x.1).then(y.0.cmp(&x.0)));
+            rows
+        };
+        let mut systems = BTreeMap::new();
+        let mut timeline = BTreeMap::new();
+        let mut clash = |kills: &[Kill], damage, a_side: bool| {
+            let mut ships = BTreeMap::new();
+            for &(system, timestamp, ship) in kills {
+                *ships.entry(ship).or_insert(0) += 1;
+                *systems.entry(system).or_insert(0) += 1;
+                let day: &mut (i64, i64) = timeline.entry(rollup::day(timestamp)).or_default();
+                if a_side {
+                    day.0 += 1;
+                } else {
+                    day.1 += 1;
+                }
+            }
+            Clash {
+                kills: kills.len() as i64,
+                damage,
+                ships: ranked(ships),
+            }
+        };
+        let a = clash(&kills_a, damage_a, true);
+        let b = clash(&kills_b, damage_b, false);
+
+        Ok(Versus {
+            a,
+            b,
+            systems: ranked(systems),
+            timeline: timeline
+                .into_iter()
+                .map(|(day, (a, b))| (day, a, b))
+                .collect(),
+        })
+    }
+
+    fn clash(
+        &self,
+        killer: SubjectType,
+        victim: SubjectType,
+        interval: Interval,
+    ) -> anyhow::Result<(Vec<Kill>, i64)> {
+        use diesel::dsl::sum;
+        use schema::{attackers, killmails, victims};
+
+        self.reader().and_then(|mut conn| {
+            let kills = killmails::table
+                .inner_join(victims::table.on(victims::killmail_id.eq(killmails::killmail_id)))
+                .filter(victim_filter(victim))
+                .filter(
+                    killmails::killmail_id.eq_any(
+                        attackers::table
+                            .filter(attacker_filter(killer))
+                            .select(attackers::killmail_id),
+                    ),
+                )
+                .filter(killmails::killmail_timestamp.ge(interval.start()))
+                .filter(killmails::killmail_timestamp.lt(interval.end()))
+                .select((
+                    killmails::solar_system_id,
+                    killmails::killmail_timestamp,
+                    victims::ship_type_id,
+                ))
+                .load::<(i32, i64, i32)>(&mut *conn)?;
+            let damage = attackers::table
+                .inner_join(killmails::table.on(killmails::killmail_id.eq(attackers::killmail_id)))
+                .inner_join(victims::table.on(victims::killmail_id.eq(attackers::killmail_id)))
+                .filter(attacker_filter(killer))
+                .filter(victim_filter(victim))
+                .filter(killmails::killmail_timestamp.ge(interval.start()))
+                .filter(killmails::killmail_timestamp.lt(interval.end()))
+                .select(sum(attackers::damage_done))
+                .first::<Option<i64>>(&mut *conn)?;
+            Ok((kills, damage.unwrap_or_default()))
+        })
+    }
+
+    pub fn war_kills(
+        &self,
+        war: i32,
+        losers: &[i32],
+        interval: Interval,
+    ) -> anyhow::Result<(i64, Option<f64>)> {
+        use diesel::dsl::{count, sum};
+        use schema::{killmails, victims, zkb};
+
+        self.reader().and_then(|mut conn| {
+            killmails::table
+                .inner_join(victims::table.on(victims::killmail_id.eq(killmails::killmail_id)))
+                .left_join(zkb::table.on(zkb::killmail_id.eq(killmails::killmail_id)))
+                .filter(killmails::war_id.eq(war))
+                .filter(
+                    victims::corporation_id
+                        .eq_any(losers)
+                        .or(victims::alliance_id.eq_any(losers)),
+                )
+                .filter(killmails::killmail_timestamp.ge(interval.start()))
+                .filter(killmails::killmail_timestamp.lt(interval.end()))
+                .select((
+                    count(killmails::killmail_id),
+                    sum(zkb::total_value.nullable()),
+                ))
+                .first::<(i64, Option<f64>)>(&mut *conn)
+                .map_err(|e| anyhow::anyhow!("{e}"))
+        })
+    }
+
+    pub fn war_pilots(
+        &self,
+        war: i32,
+        losers: &[i32],
+        interval: Interval,
+        page: Page,
+    ) -> anyhow::Result<Paged<(i32, i64)>> {
+        use diesel::dsl::count;
+        use schema::{attackers, killmails, victims};
+
+        let query = || {
+            attackers::table
+                .inner_join(killmails::table.on(killmails::killmail_id.eq(attackers::killmail_id)))
+                .inner_join(victims::table.on(victims::killmail_id.eq(attackers::killmail_id)))
+                .filter(killmails::war_id.eq(war))
+                .filter(
+                    victims::corporation_id
+                        .eq_any(losers)
+                        .or(victims::alliance_id.eq_any(losers)),
+                )
+                .filter(attackers::character_id.ne(0))
+                .filter(killmails::killmail_timestamp.ge(interval.start()))
+                .filter(killmails::killmail_timestamp.lt(interval.end()))
+        };
+
+        self.reader().and_then(|mut conn| {
+            Ok(paged_groups!(
+                &mut *conn,
+                query,
+                attackers::character_id,
+                count(attackers::killmail_id),
+                page
+            ))
+        })
+    }
+
+    pub fn war_timeline(
+        &self,
+        war: i32,
+        losers: &[i32],
+        interval: Interval,
+    ) -> anyhow::Result<Vec<(i64, i64)>> {
+        use schema::{killmails, victims};
+
+        let timestamps = self.reader().and_then(|mut conn| {
+            killmails::table
+                .inner_join(victims::table.on(victims::killmail_id.eq(killmails::killmail_id)))
+                .filter(killmails::war_id.eq(war))
+                .filter(
+                    victims::corporation_id
+                        .eq_any(losers)
+                        .or(victims::alliance_id.eq_any(losers)),
+                )
+                .filter(killmails::killmail_timestamp.ge(interval.start()))
+                .filter(killmails::killmail_timestamp.lt(interval.end()))
+                .select(killmails::killmail_timestamp)
+                .load::<i64>(&mut *conn)
+                .map_err(|e| anyhow::anyhow!("{e}"))
+        })?;
 
-        let mut hours = HashMap::new();
-        for date in dates {
-            let datetime = NaiveDateTime::parse_from_str(&date, DATETIME)?;
-            *hours.entry(datetime.hour()).or_insert(0) += 1;
+        let mut days = BTreeMap::new();
+        for timestamp in timestamps {
+            *days.entry(rollup::day(timestamp)).or_insert(0) += 1;
         }
 
-        Ok(hours)
+        Ok(days.into_iter().collect())
     }
 }
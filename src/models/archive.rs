@@ -0,0 +1,174 @@
+use crate::killmails::Killmail;
+
+use chrono::NaiveDate;
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+use std::collections::{BTreeMap, HashSet};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+const DATE: &str = "%Y-%m-%d";
+const PREFIX: &str = "killmails-";
+const SUFFIX: &str = ".jsonl.gz";
+
+#[derive(Debug, Clone)]
+pub struct Archive {
+    dir: PathBuf,
+}
+impl Archive {
+    pub fn new<P: AsRef<Path>>(dir: P) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+        }
+    }
+
+    pub fn path(&self, date: &NaiveDate) -> PathBuf {
+        self.dir
+            .join(format!("{PREFIX}{}{SUFFIX}", date.format(DATE)))
+    }
+
+    pub fn dates(
+        &self,
+        from: Option<NaiveDate>,
+        to: Option<NaiveDate>,
+    ) -> anyhow::Result<Vec<NaiveDate>> {
+        let mut dates = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let name = entry?.file_name().to_string_lossy().to_string();
+            let date = name
+                .strip_prefix(PREFIX)
+                .and_then(|name| name.strip_suffix(SUFFIX))
+                .and_then(|date| NaiveDate::parse_from_str(date, DATE).ok());
+            if let Some(date) = date {
+                if from.is_none_or(|from| from <= date) && to.is_none_or(|to| date <= to) {
+                    dates.push(date);
+                }
+            }
+        }
+        dates.sort();
+        Ok(dates)
+    }
+
+    pub fn read(&self, date: &NaiveDate) -> anyhow::Result<Vec<Killmail>> {
+        let path = self.path(date);
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        let reader = BufReader::new(MultiGzDecoder::new(File::open(path)?));
+        let mut killmails = Vec::new();
+        for line in reader.lines() {
+            let line = line?;
+            if !line.trim().is_empty() {
+                killmails.push(serde_json::from_str::<Killmail>(&line)?);
+            }
+        }
+        Ok(killmails)
+    }
+
+    pub fn write(&self, killmails: &[Killmail]) -> anyhow::Result<usize> {
+        let mut days: BTreeMap<NaiveDate, Vec<&Killmail>> = BTreeMap::new();
+        for killmail in killmails {
+            let date = killmail.killmail_time.get(..10).unwrap_or_default();
+            let date = NaiveDate::parse_from_str(date, DATE)?;
+            days.entry(date).or_default().push(killmail);
+        }
+
+        fs::create_dir_all(&self.dir)?;
+        let mut count = 0;
+        for (date, killmails) in days {
+            let archived = self
+                .read(&date)?
+                .into_iter()
+                .map(|killmail| killmail.killmail_id)
+                .collect::<HashSet<i32>>();
+            let killmails = killmails
+                .into_iter()
+                .filter(|killmail| !archived.contains(&killmail.killmail_id))
+                .collect::<Vec<&Killmail>>();
+            if killmails.is_empty() {
+                continue;
+            }
+
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(self.path(&date))?;
+            let mut encoder = GzEncoder::new(file, Compression::default());
+            for killmail in &killmails {
+                writeln!(encoder, "{}", serde_json::to_string(killmail)?)?;
+            }
+            encoder.finish()?;
+            count += killmails.len();
+        }
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn killmail(id: i32, time: &str) -> Killmail {
+        Killmail {
+            killmail_id: id,
+            killmail_time: time.to_string(),
+            solar_system_id: 30000142,
+            ..Default::default()
+        }
+    }
+
+    fn archive(name: &str) -> Archive {
+        let dir = std::env::temp_dir().join(format!("evetech-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        Archive::new(dir)
+    }
+
+    #[test]
+    fn write_and_read() -> anyhow::Result<()> {
+        let archive = archive("write");
+        let day = NaiveDate::from_ymd_opt(2024, 8, 27).unwrap();
+
+        let first = vec![
+            killmail(1, "2024-08-27T03:54:10Z"),
+            killmail(2, "2024-08-27T23:59:59Z"),
+            killmail(3, "2024-08-28T00:00:00Z"),
+        ];
+        assert_eq!(archive.write(&first)?, 3);
+        assert_eq!(archive.write(&first)?, 0);
+        assert_eq!(archive.write(&[killmail(4, "2024-08-27T12:00:00Z")])?, 1);
+
+        let ids = archive
+            .read(&day)?
+            .into_iter()
+            .map(|killmail| killmail.killmail_id)
+            .collect::<Vec<i32>>();
+        assert_eq!(ids, vec![1, 2, 4]);
+        assert_eq!(archive.read(&day)?[0], first[0]);
+        assert!(archive.read(&NaiveDate::from_ymd_opt(2024, 8, 1).unwrap())?.is_empty());
+
+        fs::remove_dir_all(&archive.dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn dates() -> anyhow::Result<()> {
+        let archive = archive("dates");
+        archive.write(&[
+            killmail(1, "2024-08-26T00:00:00Z"),
+            killmail(2, "2024-08-27T00:00:00Z"),
+            killmail(3, "2024-08-28T00:00:00Z"),
+        ])?;
+        fs::write(archive.dir.join("notes.txt"), "")?;
+
+        let date = |d| NaiveDate::from_ymd_opt(2024, 8, d).unwrap();
+        assert_eq!(archive.dates(None, None)?, vec![date(26), date(27), date(28)]);
+        assert_eq!(archive.dates(Some(date(27)), None)?, vec![date(27), date(28)]);
+        assert_eq!(archive.dates(None, Some(date(26)))?, vec![date(26)]);
+
+        fs::remove_dir_all(&archive.dir)?;
+        Ok(())
+    }
+}
@@ -5,10 +5,15 @@ use super::as_option;
 
 #[derive(Queryable, Selectable, Insertable)]
 #[diesel(table_name = crate::schema::attackers)]
-#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+#[cfg_attr(feature = "postgres", diesel(check_for_backend(diesel::pg::Pg)))]
+#[cfg_attr(
+    not(feature = "postgres"),
+    diesel(check_for_backend(diesel::sqlite::Sqlite))
+)]
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Default)]
 pub struct Attacker {
     pub killmail_id: i32,
+    pub attacker_index: i32,
     pub character_id: i32,
     pub corporation_id: i32,
     pub alliance_id: i32,
@@ -19,10 +24,11 @@ pub struct Attacker {
     pub ship_type_id: i32,
     pub weapon_type_id: i32,
 }
-impl From<(i32, &killmails::Attacker)> for Attacker {
-    fn from((id, attacker): (i32, &killmails::Attacker)) -> Self {
+impl From<(i32, i32, &killmails::Attacker)> for Attacker {
+    fn from((id, index, attacker): (i32, i32, &killmails::Attacker)) -> Self {
         Attacker {
             killmail_id: id,
+            attacker_index: index,
             character_id: attacker.character_id.unwrap_or_default(),
             corporation_id: attacker.corporation_id.unwrap_or_default(),
             alliance_id: attacker.alliance_id.unwrap_or_default(),
@@ -0,0 +1,76 @@
+use crate::killmails;
+use diesel::prelude::*;
+
+#[derive(Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::items)]
+#[cfg_attr(feature = "postgres", diesel(check_for_backend(diesel::pg::Pg)))]
+#[cfg_attr(
+    not(feature = "postgres"),
+    diesel(check_for_backend(diesel::sqlite::Sqlite))
+)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Default)]
+pub struct Item {
+    pub killmail_id: i32,
+    pub item_index: i32,
+    pub parent_index: Option<i32>,
+    pub flag: i32,
+    pub item_type_id: i32,
+    pub quantity_destroyed: Option<i64>,
+    pub quantity_dropped: Option<i64>,
+    pub singleton: i32,
+}
+impl Item {
+    pub fn flatten(id: i32, items: &[killmails::Item]) -> Vec<Item> {
+        let mut flat = Vec::new();
+        Self::flatten_into(id, items, None, &mut flat);
+        flat
+    }
+
+    fn flatten_into(
+        id: i32,
+        items: &[killmails::Item],
+        parent_index: Option<i32>,
+        flat: &mut Vec<Item>,
+    ) {
+        for item in items {
+            let item_index = flat.len() as i32;
+            flat.push(Item {
+                killmail_id: id,
+                item_index,
+                parent_index,
+                flag: item.flag,
+                item_type_id: item.item_type_id,
+                quantity_destroyed: item.quantity_destroyed.and_then(|x| x.try_into().ok()),
+                quantity_dropped: item.quantity_dropped.and_then(|x| x.try_into().ok()),
+                singleton: item.singleton,
+            });
+            if let Some(items) = &item.items {
+                Self::flatten_into(id, items, Some(item_index), flat);
+            }
+        }
+    }
+
+    pub fn nest(items: &[Item]) -> Option<Vec<killmails::Item>> {
+        Self::children(items, None)
+    }
+
+    fn children(items: &[Item], parent_index: Option<i32>) -> Option<Vec<killmails::Item>> {
+        let children = items
+            .iter()
+            .filter(|item| item.parent_index == parent_index)
+            .map(|item| killmails::Item {
+                flag: item.flag,
+                item_type_id: item.item_type_id,
+                quantity_destroyed: item.quantity_destroyed.and_then(|x| x.try_into().ok()),
+                quantity_dropped: item.quantity_dropped.and_then(|x| x.try_into().ok()),
+                singleton: item.singleton,
+                items: Self::children(items, Some(item.item_index)),
+            })
+            .collect::<Vec<killmails::Item>>();
+        if children.is_empty() {
+            None
+        } else {
+            Some(children)
+        }
+    }
+}
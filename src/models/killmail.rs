@@ -3,7 +3,11 @@ use diesel::prelude::*;
 
 #[derive(Queryable, Selectable, Insertable)]
 #[diesel(table_name = crate::schema::killmails)]
-#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+#[cfg_attr(feature = "postgres", diesel(check_for_backend(diesel::pg::Pg)))]
+#[cfg_attr(
+    not(feature = "postgres"),
+    diesel(check_for_backend(diesel::sqlite::Sqlite))
+)]
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Default)]
 pub struct Killmail {
     pub killmail_id: i32,
@@ -11,16 +15,20 @@ pub struct Killmail {
     pub solar_system_id: i32,
     pub moon_id: Option<i32>,
     pub war_id: Option<i32>,
+    pub killmail_timestamp: i64,
 }
 
-impl From<&killmails::Killmail> for Killmail {
-    fn from(killmail: &killmails::Killmail) -> Self {
-        Killmail {
+impl TryFrom<&killmails::Killmail> for Killmail {
+    type Error = anyhow::Error;
+
+    fn try_from(killmail: &killmails::Killmail) -> anyhow::Result<Self> {
+        Ok(Killmail {
             killmail_id: killmail.killmail_id,
             killmail_time: killmail.killmail_time.clone(),
             solar_system_id: killmail.solar_system_id,
             moon_id: killmail.moon_id.map(|x| x.try_into().ok()).flatten(),
             war_id: killmail.war_id.map(|x| x.try_into().ok()).flatten(),
-        }
+            killmail_timestamp: killmail.timestamp()?,
+        })
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,15 @@
+use diesel::prelude::*;
+
+#[derive(Queryable, Selectable, Insertable, AsChangeset)]
+#[diesel(table_name = crate::schema::locations, primary_key(system_id))]
+#[cfg_attr(feature = "postgres", diesel(check_for_backend(diesel::pg::Pg)))]
+#[cfg_attr(
+    not(feature = "postgres"),
+    diesel(check_for_backend(diesel::sqlite::Sqlite))
+)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Default)]
+pub struct Location {
+    pub system_id: i32,
+    pub constellation_id: i32,
+    pub region_id: i32,
+}
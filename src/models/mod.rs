@@ -1,10 +1,48 @@
+/// Loads one page of `(group, rank)` rows, ordered by the aggregate `$rank` and then by `$group`,
+/// along with the number of groups. `$query` builds the filtered query once per statement.
+macro_rules! paged_groups {
+    ($conn:expr, $query:expr, $group:expr, $rank:expr, $page:expr) => {{
+        let total = $query()
+            .select(diesel::dsl::count($group).aggregate_distinct())
+            .first::<i64>($conn)?;
+        let rows = $query().group_by($group).select(($group, $rank));
+        let limit = $page.limit.unwrap_or(i64::MAX);
+        let rows = match $page.order {
+            $crate::models::Order::Desc => rows
+                .order(($rank.desc(), $group.desc()))
+                .limit(limit)
+                .offset($page.offset)
+                .load($conn)?,
+            $crate::models::Order::Asc => rows
+                .order(($rank.asc(), $group.asc()))
+                .limit(limit)
+                .offset($page.offset)
+                .load($conn)?,
+        };
+        $crate::models::Paged { rows, total }
+    }};
+}
+
 pub mod api;
+pub mod archive;
 mod attacker;
+mod item;
 mod killmail;
+mod location;
+mod name;
+mod pool;
+mod rollup;
 mod victim;
+mod war_side;
+mod zkb;
 
 pub use api::Api;
-pub use api::{ObjectType, SubjectType};
+pub use api::{
+    Activity, Area, Clash, Gang, Interval, KillFilter, ObjectType, Order, Page, Paged, SaveStatus,
+    SubjectType, Versus,
+};
+pub use archive::Archive;
+pub use location::Location;
 
 fn as_option(x: i32) -> Option<i32> {
     if 0 == x {
@@ -17,24 +55,80 @@ fn as_option(x: i32) -> Option<i32> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::apps::danger::{Danger, ON_GRID};
+    use crate::apps::waypoint::WayPoint;
+    use crate::common::Position;
     use crate::killmails;
 
     use diesel::prelude::*;
-    use diesel::sqlite::SqliteConnection;
     use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
+    use pool::DbConnection;
+    use std::sync::atomic::{AtomicUsize, Ordering};
 
+    #[cfg(not(feature = "postgres"))]
     const MIGRATIONS: EmbeddedMigrations = embed_migrations!();
-    const MEMORY: &str = ":memory:";
+    #[cfg(feature = "postgres")]
+    const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations_postgres");
+
+    const ALL: Interval = Interval {
+        from: None,
+        to: None,
+    };
+    const PAGE: Page = Page {
+        limit: None,
+        offset: 0,
+        order: Order::Desc,
+    };
 
-    fn establish_connection<S: Into<String>>(uri: S) -> anyhow::Result<SqliteConnection> {
-        let conn = SqliteConnection::establish(uri.into().as_str())?;
+    fn establish_connection<S: Into<String>>(uri: S) -> anyhow::Result<DbConnection> {
+        let conn = DbConnection::establish(uri.into().as_str())?;
         Ok(conn)
     }
 
-    pub fn run_migrations(conn: &mut SqliteConnection) {
+    pub fn run_migrations(conn: &mut DbConnection) {
         conn.run_pending_migrations(MIGRATIONS).unwrap();
     }
 
+    /// The api under test, the database files are removed along with it.
+    struct TestApi {
+        api: Api,
+        _dir: Option<tempfile::TempDir>,
+    }
+    impl std::ops::Deref for TestApi {
+        type Target = Api;
+
+        fn deref(&self) -> &Api {
+            &self.api
+        }
+    }
+
+    #[cfg(not(feature = "postgres"))]
+    fn create_database(_: usize) -> anyhow::Result<(String, Option<tempfile::TempDir>)> {
+        let dir = tempfile::Builder::new().prefix("evetech-killmails-").tempdir()?;
+        let path = dir.path().join("killmails.db");
+        Ok((path.display().to_string(), Some(dir)))
+    }
+
+    #[cfg(feature = "postgres")]
+    fn create_database(id: usize) -> anyhow::Result<(String, Option<tempfile::TempDir>)> {
+        let server = std::env::var("ZKBINFO_TEST_SERVER")
+            .unwrap_or(String::from("postgres://postgres@localhost"));
+        let name = format!("killmails_test_{id}");
+        let mut conn = establish_connection(format!("{server}/postgres"))?;
+        diesel::sql_query(format!("DROP DATABASE IF EXISTS {name}")).execute(&mut conn)?;
+        diesel::sql_query(format!("CREATE DATABASE {name}")).execute(&mut conn)?;
+        Ok((format!("{server}/{name}"), None))
+    }
+
+    fn create_api() -> anyhow::Result<TestApi> {
+        static DATABASES: AtomicUsize = AtomicUsize::new(0);
+        let (uri, dir) = create_database(DATABASES.fetch_add(1, Ordering::SeqCst))?;
+        let api = Api::new(&uri, 2)?;
+        let mut conn = establish_connection(&uri)?;
+        run_migrations(&mut conn);
+        Ok(TestApi { api, _dir: dir })
+    }
+
     fn create_killmail(id: i32) -> killmails::killmail::Killmail {
         killmails::killmail::Killmail {
             killmail_id: id,
@@ -53,6 +147,7 @@ mod tests {
                 ship_type_id: 42,
                 items: None,
             },
+            zkb: None,
         }
     }
 
@@ -70,7 +165,7 @@ mod tests {
         }
     }
 
-    fn generate_killmails(api: &mut Api, count: i32) -> anyhow::Result<()> {
+    fn generate_killmails(api: &Api, count: i32) -> anyhow::Result<()> {
         const OFFSET: i32 = 2;
         for i in 0..count {
             let mut killmail = create_killmail(i + OFFSET);
@@ -86,30 +181,52 @@ mod tests {
 
     #[test]
     fn friends() -> anyhow::Result<()> {
-        let mut conn = establish_connection(MEMORY)?;
-        run_migrations(&mut conn);
-        let mut api = Api::new(conn);
-        generate_killmails(&mut api, 4)?;
+        let api = create_api()?;
+        generate_killmails(&api, 4)?;
 
-        let assist = api.friends(SubjectType::Character(2), ObjectType::Character)?;
+        let assist = api
+            .friends(SubjectType::Character(2), ObjectType::Character, ALL, PAGE)?
+            .rows;
         assert_eq!(assist, vec![(5, 1), (4, 1), (3, 1)]);
 
-        let assist = api.friends(SubjectType::Character(3), ObjectType::Character)?;
+        let assist = api
+            .friends(SubjectType::Character(3), ObjectType::Character, ALL, PAGE)?
+            .rows;
         assert_eq!(assist, vec![(5, 2), (4, 2), (2, 1)]);
 
-        let assist = api.friends(SubjectType::Character(4), ObjectType::Character)?;
+        let assist = api
+            .friends(SubjectType::Character(4), ObjectType::Character, ALL, PAGE)?
+            .rows;
         assert_eq!(assist, vec![(5, 3), (3, 2), (2, 1)]);
 
-        let assist = api.friends(SubjectType::Corporation(40), ObjectType::Corporation)?;
+        let assist = api
+            .friends(
+                SubjectType::Corporation(40),
+                ObjectType::Corporation,
+                ALL,
+                PAGE,
+            )?
+            .rows;
         assert_eq!(assist, vec![(50, 3), (30, 2), (20, 1)]);
 
-        let assist = api.friends(SubjectType::Alliance(400), ObjectType::Alliance)?;
+        let assist = api
+            .friends(SubjectType::Alliance(400), ObjectType::Alliance, ALL, PAGE)?
+            .rows;
         assert_eq!(assist, vec![(500, 3), (300, 2), (200, 1)]);
 
-        let assist = api.friends(SubjectType::Faction(4000), ObjectType::Faction)?;
+        let assist = api
+            .friends(SubjectType::Faction(4000), ObjectType::Faction, ALL, PAGE)?
+            .rows;
         assert_eq!(assist, vec![(5000, 3), (3000, 2), (2000, 1)]);
 
-        let assist = api.friends(SubjectType::Corporation(20), ObjectType::Character)?;
+        let assist = api
+            .friends(
+                SubjectType::Corporation(20),
+                ObjectType::Character,
+                ALL,
+                PAGE,
+            )?
+            .rows;
         assert_eq!(assist, vec![(5, 1), (4, 1), (3, 1)]);
 
         Ok(())
@@ -117,30 +234,57 @@ mod tests {
 
     #[test]
     fn enemies() -> anyhow::Result<()> {
-        let mut conn = establish_connection(MEMORY)?;
-        run_migrations(&mut conn);
-        let mut api = Api::new(conn);
-        generate_killmails(&mut api, 4)?;
+        let api = create_api()?;
+        generate_killmails(&api, 4)?;
 
-        let assist = api.enemies(SubjectType::Character(1), ObjectType::Character)?;
+        let assist = api
+            .enemies(SubjectType::Character(1), ObjectType::Character, ALL, PAGE)?
+            .rows;
         assert_eq!(assist, vec![(5, 4), (4, 3), (3, 2), (2, 1)]);
 
-        let assist = api.enemies(SubjectType::Corporation(10), ObjectType::Corporation)?;
+        let assist = api
+            .enemies(
+                SubjectType::Corporation(10),
+                ObjectType::Corporation,
+                ALL,
+                PAGE,
+            )?
+            .rows;
         assert_eq!(assist, vec![(50, 4), (40, 3), (30, 2), (20, 1)]);
 
-        let assist = api.enemies(SubjectType::Alliance(100), ObjectType::Alliance)?;
+        let assist = api
+            .enemies(SubjectType::Alliance(100), ObjectType::Alliance, ALL, PAGE)?
+            .rows;
         assert_eq!(assist, vec![(500, 4), (400, 3), (300, 2), (200, 1)]);
 
-        let assist = api.enemies(SubjectType::Faction(1000), ObjectType::Faction)?;
+        let assist = api
+            .enemies(SubjectType::Faction(1000), ObjectType::Faction, ALL, PAGE)?
+            .rows;
         assert_eq!(assist, vec![(5000, 4), (4000, 3), (3000, 2), (2000, 1)]);
 
-        let assist = api.enemies(SubjectType::Character(1), ObjectType::Corporation)?;
+        let assist = api
+            .enemies(
+                SubjectType::Character(1),
+                ObjectType::Corporation,
+                ALL,
+                PAGE,
+            )?
+            .rows;
         assert_eq!(assist, vec![(50, 4), (40, 3), (30, 2), (20, 1)]);
 
-        let assist = api.enemies(SubjectType::Character(1), ObjectType::Alliance)?;
+        let assist = api
+            .enemies(SubjectType::Character(1), ObjectType::Alliance, ALL, PAGE)?
+            .rows;
         assert_eq!(assist, vec![(500, 4), (400, 3), (300, 2), (200, 1)]);
 
-        let assist = api.enemies(SubjectType::Corporation(10), ObjectType::Alliance)?;
+        let assist = api
+            .enemies(
+                SubjectType::Corporation(10),
+                ObjectType::Alliance,
+                ALL,
+                PAGE,
+            )?
+            .rows;
         assert_eq!(assist, vec![(500, 4), (400, 3), (300, 2), (200, 1)]);
 
         Ok(())
@@ -148,8 +292,7 @@ mod tests {
 
     #[test]
     fn load() -> anyhow::Result<()> {
-        let mut conn = establish_connection(MEMORY)?;
-        run_migrations(&mut conn);
+        let api = create_api()?;
 
         let killmail = killmails::killmail::Killmail {
             killmail_id: 120461567,
@@ -199,13 +342,69 @@ mod tests {
                 alliance_id: Some(1900696668),
                 faction_id: None,
                 damage_taken: 11342,
-                position: None, // Unsupported in DB
+                position: Some(Position::new(
+                    -955007564796.388,
+                    -126124010916.492,
+                    726635633538.084,
+                )),
                 ship_type_id: 81008,
-                items: None, // Unsupported in DB
+                items: Some(vec![
+                    killmails::item::Item {
+                        flag: 93,
+                        item_type_id: 31724,
+                        quantity_destroyed: Some(1),
+                        quantity_dropped: None,
+                        singleton: 0,
+                        items: None,
+                    },
+                    killmails::item::Item {
+                        flag: 5,
+                        item_type_id: 3467,
+                        quantity_destroyed: None,
+                        quantity_dropped: Some(1),
+                        singleton: 0,
+                        items: Some(vec![
+                            killmails::item::Item {
+                                flag: 0,
+                                item_type_id: 34,
+                                quantity_destroyed: Some(5_000_000_000),
+                                quantity_dropped: None,
+                                singleton: 0,
+                                items: None,
+                            },
+                            killmails::item::Item {
+                                flag: 0,
+                                item_type_id: 35,
+                                quantity_destroyed: None,
+                                quantity_dropped: Some(1000),
+                                singleton: 0,
+                                items: None,
+                            },
+                        ]),
+                    },
+                    killmails::item::Item {
+                        flag: 185,
+                        item_type_id: 81144,
+                        quantity_destroyed: Some(1924),
+                        quantity_dropped: None,
+                        singleton: 0,
+                        items: None,
+                    },
+                ]),
             },
+            zkb: Some(killmails::zkb::Zkb {
+                hash: "a0b1c2d3e4f5".to_owned(),
+                total_value: 65374.93,
+                fitted_value: 10000.0,
+                dropped_value: 2540.5,
+                points: 1,
+                npc: false,
+                solo: false,
+                awox: false,
+                labels: vec!["cat:6".to_owned(), "pvp".to_owned()],
+            }),
         };
 
-        let mut api = Api::new(conn);
         api.save(&killmail)?;
 
         let selected = api.load(120461567)?;
@@ -222,16 +421,573 @@ mod tests {
             .iter()
             .all(|item| killmail.attackers.contains(item)));
         assert_eq!(killmail.victim, selected.victim);
+        assert_eq!(killmail.zkb, selected.zkb);
+
+        Ok(())
+    }
+
+    #[test]
+    fn load_without_items() -> anyhow::Result<()> {
+        let api = create_api()?;
+
+        let killmail = create_killmail(2);
+        api.save(&killmail)?;
+
+        assert_eq!(killmail, api.load(2)?);
+
+        Ok(())
+    }
+
+    fn create_npc(faction_id: i32, ship_type_id: i32) -> killmails::attacker::Attacker {
+        killmails::attacker::Attacker {
+            character_id: None,
+            corporation_id: Some(1000274),
+            alliance_id: None,
+            faction_id: Some(faction_id),
+            damage_done: 100,
+            final_blow: false,
+            security_status: 0.0,
+            ship_type_id: Some(ship_type_id),
+            weapon_type_id: None,
+        }
+    }
+
+    #[test]
+    fn load_npc_attackers() -> anyhow::Result<()> {
+        let api = create_api()?;
+
+        let mut killmail = create_killmail(2);
+        killmail.victim.character_id = None;
+        killmail.attackers = vec![
+            create_npc(500024, 34495),
+            create_npc(500024, 34495),
+            create_npc(500024, 34495),
+        ];
+        killmail.attackers[0].final_blow = true;
+        api.save(&killmail)?;
+
+        assert_eq!(api.load(2)?, killmail);
+        assert_eq!(api.wins(SubjectType::Faction(500024), ALL)?, (3, Some(300)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn load_mixed_attackers() -> anyhow::Result<()> {
+        let api = create_api()?;
+
+        let mut killmail = create_killmail(2);
+        let mut drone = create_attacker(3);
+        drone.ship_type_id = Some(2488);
+        let mut structure = create_npc(0, 35832);
+        structure.faction_id = None;
+        killmail.attackers = vec![
+            create_attacker(3),
+            drone,
+            create_attacker(4),
+            structure.clone(),
+            structure,
+            create_npc(500024, 34495),
+            create_npc(500024, 34495),
+        ];
+        api.save(&killmail)?;
+
+        assert_eq!(api.load(2)?, killmail);
+        assert_eq!(api.wins(SubjectType::Character(3), ALL)?, (2, Some(200)));
+        assert_eq!(
+            api.wins(SubjectType::Corporation(1000274), ALL)?,
+            (4, Some(400))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn ships_and_weapons() -> anyhow::Result<()> {
+        let api = create_api()?;
+
+        let armed = |id, ship, weapon| {
+            let mut attacker = create_attacker(id);
+            attacker.ship_type_id = ship;
+            attacker.weapon_type_id = weapon;
+            attacker
+        };
+        let mut killmail = create_killmail(2);
+        killmail.attackers = vec![
+            armed(3, Some(100), Some(200)),
+            armed(4, Some(101), Some(201)),
+        ];
+        api.save(&killmail)?;
+        let mut killmail = create_killmail(3);
+        killmail.attackers = vec![armed(3, Some(100), Some(202))];
+        api.save(&killmail)?;
+        let mut killmail = create_killmail(4);
+        killmail.victim.character_id = Some(6);
+        killmail.victim.ship_type_id = 43;
+        killmail.attackers = vec![armed(3, Some(102), Some(200)), armed(4, None, None)];
+        api.save(&killmail)?;
+
+        assert_eq!(
+            api.ship_killers(42, ALL, PAGE)?.rows,
+            vec![(100, 2), (101, 1)]
+        );
+        assert_eq!(api.ship_killers(43, ALL, PAGE)?.rows, vec![(102, 1)]);
+        assert_eq!(
+            api.weapons(SubjectType::Character(3), ALL, PAGE)?.rows,
+            vec![(200, 2), (202, 1)]
+        );
+        assert_eq!(api.weapons(SubjectType::Character(4), ALL, PAGE)?.total, 1);
+        let rq = SubjectType::Character(3);
+        assert_eq!(
+            api.ships_against(rq, SubjectType::Character(1), ALL, PAGE)?
+                .rows,
+            vec![(100, 2)]
+        );
+        assert_eq!(
+            api.ships_against(rq, SubjectType::Character(6), ALL, PAGE)?
+                .rows,
+            vec![(102, 1)]
+        );
+        assert_eq!(
+            api.ships_against(
+                SubjectType::Corporation(30),
+                SubjectType::Corporation(10),
+                ALL,
+                PAGE
+            )?
+            .rows,
+            vec![(100, 2), (102, 1)]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn gang() -> anyhow::Result<()> {
+        let api = create_api()?;
+        generate_killmails(&api, 4)?;
+
+        let gang = api.gang(SubjectType::Character(5), ALL)?;
+        assert_eq!(gang.kills, 4);
+        assert_eq!(gang.sizes, vec![(1, 1), (2, 1), (3, 1), (4, 1)]);
+        assert_eq!((gang.solo, gang.median, gang.members), (0.25, 2.5, 1.0));
+
+        let mut killmail = create_killmail(6);
+        killmail.attackers = vec![create_attacker(3), create_attacker(3)];
+        api.save(&killmail)?;
+        let gang = api.gang(SubjectType::Corporation(30), ALL)?;
+        assert_eq!(gang.sizes, vec![(2, 1), (3, 1), (4, 1)]);
+        assert_eq!(
+            (gang.solo, gang.median, gang.members),
+            (0.0, 3.0, 4.0 / 3.0)
+        );
+
+        assert_eq!(api.gang(SubjectType::Character(1), ALL)?, Gang::default());
+
+        Ok(())
+    }
+
+    #[test]
+    fn versus() -> anyhow::Result<()> {
+        let api = create_api()?;
+        generate_killmails(&api, 4)?;
+
+        let mut killmail = create_killmail(6);
+        killmail.solar_system_id = 2;
+        killmail.victim.character_id = Some(5);
+        killmail.victim.ship_type_id = 43;
+        killmail.attackers = vec![create_attacker(1)];
+        api.save(&killmail)?;
+
+        let day = |d| {
+            chrono::NaiveDate::from_ymd_opt(2024, 8, d)
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+                .map(|time| time.and_utc().timestamp())
+                .unwrap()
+        };
+        let versus = api.versus(SubjectType::Character(5), SubjectType::Character(1), ALL)?;
+        assert_eq!(
+            versus.a,
+            Clash {
+                kills: 4,
+                damage: 400,
+                ships: vec![(42, 4)]
+            }
+        );
+        assert_eq!(
+            versus.b,
+            Clash {
+                kills: 1,
+                damage: 100,
+                ships: vec![(43, 1)]
+            }
+        );
+        assert_eq!(versus.systems, vec![(1, 4), (2, 1)]);
+        assert_eq!(
+            versus.timeline,
+            vec![
+                (day(2), 1, 0),
+                (day(3), 1, 0),
+                (day(4), 1, 0),
+                (day(5), 1, 0),
+                (day(6), 0, 1)
+            ]
+        );
+
+        let versus = api.versus(
+            SubjectType::Corporation(10),
+            SubjectType::Corporation(30),
+            Interval::new(Some(day(3)), None),
+        )?;
+        assert_eq!(versus.a, Clash::default());
+        assert_eq!((versus.b.kills, versus.b.damage), (1, 100));
+        assert_eq!(versus.timeline, vec![(day(3), 0, 1)]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn hotspots() -> anyhow::Result<()> {
+        let api = create_api()?;
+        generate_killmails(&api, 4)?;
+        for (id, system) in [(6, 2), (7, 2), (8, 3)] {
+            let mut killmail = create_killmail(id);
+            killmail.solar_system_id = system;
+            api.save(&killmail)?;
+        }
+
+        assert_eq!(
+            api.hotspots(Area::System, ALL, PAGE)?.rows,
+            vec![(1, 4), (2, 2), (3, 1)]
+        );
+        assert_eq!(api.hotspots(Area::Region, ALL, PAGE)?.total, 0);
+        assert_eq!(api.unlocated(&[0, 1, 2, 3])?, vec![1, 2, 3]);
+
+        let locations = [(1, 10, 100), (2, 20, 100), (3, 30, 200)].map(
+            |(system_id, constellation_id, region_id)| Location {
+                system_id,
+                constellation_id,
+                region_id,
+            },
+        );
+        assert_eq!(api.save_locations(&locations)?, 3);
+        assert!(api.unlocated(&api.stored_systems()?)?.is_empty());
+        assert_eq!(
+            api.hotspots(Area::Constellation, ALL, PAGE)?.rows,
+            vec![(10, 4), (20, 2), (30, 1)]
+        );
+        assert_eq!(
+            api.hotspots(Area::Region, ALL, PAGE)?.rows,
+            vec![(100, 6), (200, 1)]
+        );
+        let interval = Interval::new(
+            chrono::NaiveDate::from_ymd_opt(2024, 8, 6)
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+                .map(|time| time.and_utc().timestamp()),
+            None,
+        );
+        assert_eq!(
+            api.hotspots(Area::Region, interval, PAGE)?.rows,
+            vec![(100, 2), (200, 1)]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn page() -> anyhow::Result<()> {
+        let api = create_api()?;
+        generate_killmails(&api, 4)?;
+        let rq = SubjectType::Character(1);
+
+        let enemies = api.enemies(rq, ObjectType::Character, ALL, PAGE)?;
+        assert_eq!(enemies.rows, vec![(5, 4), (4, 3), (3, 2), (2, 1)]);
+        assert_eq!(enemies.total, 4);
+
+        let page = Page::new(Some(2), 1, Order::Desc);
+        let enemies = api.enemies(rq, ObjectType::Character, ALL, page)?;
+        assert_eq!(enemies.rows, vec![(4, 3), (3, 2)]);
+        assert_eq!(enemies.total, 4);
+
+        let page = Page::new(Some(2), 0, Order::Asc);
+        let ships = api.losses_ships(rq, ALL, page)?;
+        assert_eq!(ships.rows, vec![(42, 4)]);
+        assert_eq!(ships.total, 1);
+        let enemies = api.enemies(rq, ObjectType::Character, ALL, page)?;
+        assert_eq!(enemies.rows, vec![(2, 1), (3, 2)]);
+
+        let page = Page::new(Some(2), 1, Order::Asc);
+        let lost = api.lost_in_system(rq, 1, ALL, page)?;
+        let ids = lost.rows.iter().map(|kill| kill.0).collect::<Vec<i32>>();
+        assert_eq!(ids, vec![3, 4]);
+        assert_eq!(lost.total, 4);
+
+        let page = Page::new(None, 3, Order::Desc);
+        let lost = api.lost_ships(rq, 42, ALL, page)?;
+        let ids = lost.rows.iter().map(|kill| kill.0).collect::<Vec<i32>>();
+        assert_eq!(ids, vec![2]);
+        assert_eq!(lost.total, 4);
+
+        Ok(())
+    }
+
+    #[test]
+    fn kills() -> anyhow::Result<()> {
+        let api = create_api()?;
+        generate_killmails(&api, 4)?;
+        let ids = |kills: Paged<killmails::killmail::Killmail>| {
+            let ids = kills.rows.iter().map(|kill| kill.killmail_id).collect();
+            (ids, kills.total)
+        };
+
+        let all = KillFilter::default();
+        assert_eq!(ids(api.kills(&all, ALL, PAGE)?), (vec![5, 4, 3, 2], 4));
+        assert_eq!(api.kills(&all, ALL, PAGE)?.rows[1], api.load(4)?);
+
+        let page = Page::new(Some(2), 1, Order::Asc);
+        assert_eq!(ids(api.kills(&all, ALL, page)?), (vec![3, 4], 4));
+
+        let from = chrono::DateTime::parse_from_rfc3339("2024-08-04T00:00:00Z")?.timestamp();
+        let interval = Interval::new(Some(from), None);
+        assert_eq!(ids(api.kills(&all, interval, PAGE)?), (vec![5, 4], 2));
+
+        let filter = KillFilter {
+            subjects: vec![SubjectType::Character(3)],
+            ..Default::default()
+        };
+        assert_eq!(ids(api.kills(&filter, ALL, PAGE)?), (vec![3, 2], 2));
+
+        let filter = KillFilter {
+            ship: Some(42),
+            subjects: vec![SubjectType::Corporation(10), SubjectType::Alliance(400)],
+            ..Default::default()
+        };
+        assert_eq!(ids(api.kills(&filter, ALL, PAGE)?), (vec![4, 3, 2], 3));
+
+        let filter = KillFilter {
+            system: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(ids(api.kills(&filter, ALL, PAGE)?), (vec![], 0));
+
+        Ok(())
+    }
+
+    #[test]
+    fn parallel_reads() -> anyhow::Result<()> {
+        let api = std::sync::Arc::new(create_api()?);
+        generate_killmails(&api, 4)?;
+
+        let readers = (0..4)
+            .map(|_| {
+                let api = api.clone();
+                std::thread::spawn(move || {
+                    api.friends(SubjectType::Character(2), ObjectType::Character, ALL, PAGE)
+                })
+            })
+            .collect::<Vec<_>>();
+        api.save(&create_killmail(10))?;
+        for reader in readers {
+            let friends = reader
+                .join()
+                .map_err(|_| anyhow::anyhow!("Reader panicked"))??;
+            assert_eq!(friends.rows, vec![(5, 1), (4, 1), (3, 1)]);
+        }
+        assert_eq!(api.load(10)?, create_killmail(10));
+
+        Ok(())
+    }
+
+    #[test]
+    fn save() -> anyhow::Result<()> {
+        let api = create_api()?;
+
+        let mut killmail = create_killmail(2);
+        killmail.attackers.push(create_attacker(3));
+        assert_eq!(api.save(&killmail)?, SaveStatus::Inserted);
+        assert_eq!(api.save(&killmail)?, SaveStatus::Duplicate);
+
+        killmail.zkb = Some(killmails::zkb::Zkb {
+            hash: "a0b1c2d3e4f5".to_owned(),
+            ..Default::default()
+        });
+        assert_eq!(api.save(&killmail)?, SaveStatus::Updated);
+        assert_eq!(api.save(&killmail)?, SaveStatus::Duplicate);
+
+        killmail.victim.position = Some(Position::new(1.0, 2.0, 3.0));
+        killmail.victim.items = Some(vec![killmails::item::Item {
+            flag: 5,
+            item_type_id: 34,
+            quantity_dropped: Some(100),
+            ..Default::default()
+        }]);
+        assert_eq!(api.save(&killmail)?, SaveStatus::Updated);
+        assert_eq!(api.save(&killmail)?, SaveStatus::Duplicate);
+
+        let mut stale = killmail.clone();
+        stale.zkb = None;
+        stale.victim.position = None;
+        stale.victim.items = None;
+        assert_eq!(api.save(&stale)?, SaveStatus::Duplicate);
+        assert_eq!(api.load(2)?, killmail);
+
+        let mut invalid = create_killmail(4);
+        invalid.killmail_time = "yesterday".to_owned();
+        assert!(api.save(&invalid).is_err());
+        assert!(api.load(4).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn save_batch() -> anyhow::Result<()> {
+        let api = create_api()?;
+
+        api.save(&create_killmail(2))?;
+        let batch = vec![create_killmail(2), create_killmail(3), create_killmail(3)];
+        assert_eq!(
+            api.save_batch(&batch)?,
+            vec![
+                (2, SaveStatus::Duplicate),
+                (3, SaveStatus::Inserted),
+                (3, SaveStatus::Duplicate)
+            ]
+        );
+        assert_eq!(api.load(3)?, create_killmail(3));
+
+        Ok(())
+    }
+
+    #[test]
+    fn zkb() -> anyhow::Result<()> {
+        let api = create_api()?;
+
+        let mut killmail = create_killmail(2);
+        killmail.zkb = Some(killmails::zkb::Zkb {
+            hash: "a0b1c2d3e4f5".to_owned(),
+            labels: Vec::new(),
+            ..Default::default()
+        });
+        api.save(&killmail)?;
+        api.save(&create_killmail(3))?;
+
+        assert_eq!(api.zkb(2)?, killmail.zkb.unwrap());
+        assert!(api.zkb(3).is_err());
+        assert_eq!(api.load(3)?.zkb, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn archive() -> anyhow::Result<()> {
+        let api = create_api()?;
+        generate_killmails(&api, 3)?;
+        let mut recent = create_killmail(10);
+        recent.killmail_time = chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+        api.save(&recent)?;
+
+        let dir = std::env::temp_dir().join(format!("evetech-archive-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let archive = Archive::new(&dir);
+        assert_eq!(api.archive(&archive, 30)?, 3);
+        assert!(api.expired(30)?.is_empty());
+        assert!(api.load(2).is_err());
+        assert_eq!(api.load(10)?, recent);
+        assert_eq!(api.wins(SubjectType::Character(2), ALL)?, (0, None));
+
+        let date = chrono::NaiveDate::from_ymd_opt(2024, 8, 3).unwrap();
+        let archived = archive.read(&date)?;
+        assert_eq!(archived.len(), 1);
+        assert_eq!(
+            api.save_batch(&archived)?,
+            vec![(archived[0].killmail_id, SaveStatus::Inserted)]
+        );
+        assert_eq!(api.load(3)?, archived[0]);
+
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn restore() -> anyhow::Result<()> {
+        let api = create_api()?;
+        generate_killmails(&api, 3)?;
+        let saved = (2..5).map(|id| api.load(id)).collect::<anyhow::Result<Vec<_>>>()?;
+
+        let dir = std::env::temp_dir().join(format!("evetech-restore-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let archive = Archive::new(&dir);
+        assert_eq!(api.archive(&archive, 90)?, 3);
+
+        let mut restored = Vec::new();
+        for date in archive.dates(None, None)? {
+            restored.extend(api.restore(&archive, &date)?);
+        }
+        assert_eq!(
+            restored,
+            vec![
+                (2, SaveStatus::Inserted),
+                (3, SaveStatus::Inserted),
+                (4, SaveStatus::Inserted)
+            ]
+        );
+        for killmail in saved {
+            assert_eq!(api.load(killmail.killmail_id)?, killmail);
+        }
+
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn recent_kills() -> anyhow::Result<()> {
+        let api = create_api()?;
+        generate_killmails(&api, 3)?;
+
+        let now = chrono::Utc::now();
+        let belt = Position::new(1.0e9, 0.0, 0.0);
+        for (id, hours, system) in [(100, 1, 30), (101, 5, 30), (102, 2, 31), (103, 30, 30)] {
+            let mut killmail = create_killmail(id);
+            killmail.solar_system_id = system;
+            killmail.killmail_time = (now - chrono::Duration::hours(hours))
+                .format("%Y-%m-%dT%H:%M:%SZ")
+                .to_string();
+            if id == 100 {
+                killmail.victim.position = Some(Position::new(1.0e9 + 5_000.0, 0.0, 0.0));
+            }
+            api.save(&killmail)?;
+        }
+
+        let kills = api.recent_kills(&[30, 31], 24)?;
+        assert_eq!(
+            kills
+                .iter()
+                .map(|(id, system, _)| (*id, *system))
+                .collect::<Vec<(i32, i32)>>(),
+            vec![(100, 30), (102, 31), (101, 30)]
+        );
+        assert_eq!(
+            api.recent_kills(&[30], 3)?,
+            vec![(100, 30, Some(Position::new(1.0e9 + 5_000.0, 0.0, 0.0)))]
+        );
+        assert_eq!(api.recent_kills(&[1], 24)?, vec![]);
+
+        let mut danger = Danger::new(24);
+        for (killmail_id, system_id, position) in kills {
+            danger.add(system_id, killmail_id, position);
+        }
+        let waypoint = WayPoint::new(1, "Belt", &belt);
+        assert_eq!(danger.kills(30), 2);
+        assert_eq!(danger.kills_near(30, &waypoint, ON_GRID), 1);
+        assert_eq!(danger.kills_near(31, &waypoint, ON_GRID), 0);
 
         Ok(())
     }
 
     #[test]
     fn ids_by_date() -> anyhow::Result<()> {
-        let mut conn = establish_connection(MEMORY)?;
-        run_migrations(&mut conn);
-        let mut api = Api::new(conn);
-        generate_killmails(&mut api, 5)?;
+        let api = create_api()?;
+        generate_killmails(&api, 5)?;
 
         assert!(api.ids_by_date("2024-08-01")?.is_empty());
         assert_eq!(1, api.ids_by_date("2024-08-02")?.len());
@@ -243,4 +999,315 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn interval() -> anyhow::Result<()> {
+        let api = create_api()?;
+        generate_killmails(&api, 4)?;
+
+        let day = |d| {
+            chrono::NaiveDate::from_ymd_opt(2024, 8, d)
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+                .map(|time| time.and_utc().timestamp())
+        };
+        let interval = Interval::new(day(3), day(5));
+
+        assert_eq!(api.losses(SubjectType::Character(1), ALL)?, (4, Some(1000)));
+        assert_eq!(
+            api.losses(SubjectType::Character(1), interval)?,
+            (2, Some(500))
+        );
+        assert_eq!(
+            api.losses(SubjectType::Character(1), Interval::new(day(5), None))?,
+            (1, Some(100))
+        );
+        assert_eq!(api.wins(SubjectType::Character(5), ALL)?, (4, Some(400)));
+        assert_eq!(
+            api.wins(SubjectType::Character(5), interval)?,
+            (2, Some(200))
+        );
+        assert_eq!(
+            api.wins_ships(SubjectType::Character(5), interval, PAGE)?
+                .rows,
+            vec![(0, 2)]
+        );
+        assert_eq!(
+            api.losses_systems(SubjectType::Character(1), interval, PAGE)?
+                .rows,
+            vec![(1, 2)]
+        );
+
+        let enemies = api
+            .enemies(
+                SubjectType::Character(1),
+                ObjectType::Character,
+                interval,
+                PAGE,
+            )?
+            .rows;
+        assert_eq!(enemies, vec![(5, 2), (4, 2), (3, 1)]);
+        let friends = api
+            .friends(
+                SubjectType::Character(2),
+                ObjectType::Character,
+                interval,
+                PAGE,
+            )?
+            .rows;
+        assert!(friends.is_empty());
+
+        let lost = api
+            .lost_in_system(SubjectType::Character(1), 1, interval, PAGE)?
+            .rows;
+        let ids = lost.iter().map(|kill| kill.0).collect::<Vec<i32>>();
+        assert_eq!(ids, vec![4, 3]);
+        let lost = api
+            .lost_ships(SubjectType::Character(1), 42, interval, PAGE)?
+            .rows;
+        assert_eq!(lost.len(), 2);
+        let utc = chrono::FixedOffset::east_opt(0).unwrap();
+        let activity = api.activity(SubjectType::Character(5), interval, utc)?;
+        assert_eq!(activity.wins.iter().flatten().sum::<i64>(), 2);
+        assert_eq!((activity.wins[5][0], activity.wins[6][0]), (1, 1));
+        assert_eq!(activity.losses, [[0; 24]; 7]);
+        let activity = api.activity(SubjectType::Character(1), interval, utc)?;
+        assert_eq!(activity.wins, [[0; 24]; 7]);
+        assert_eq!((activity.losses[5][0], activity.losses[6][0]), (1, 1));
+        let west = chrono::FixedOffset::west_opt(3600).unwrap();
+        let activity = api.activity(SubjectType::Character(5), interval, west)?;
+        assert_eq!((activity.wins[4][23], activity.wins[5][23]), (1, 1));
+        assert_eq!(
+            api.losses(SubjectType::Character(1), Interval::days(30))?,
+            (0, None)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn rollups() -> anyhow::Result<()> {
+        let api = create_api()?;
+        generate_killmails(&api, 4)?;
+        let mut killmail = create_killmail(6);
+        killmail.solar_system_id = 2;
+        killmail.attackers = vec![create_attacker(5), create_attacker(5)];
+        killmail.attackers[1].ship_type_id = Some(670);
+        api.save(&killmail)?;
+
+        let day = |d: i64| {
+            chrono::NaiveDate::from_ymd_opt(2024, 8, 1)
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+                .map(|time| time.and_utc().timestamp() + (d - 1) * rollup::DAY)
+        };
+        let rollups = Interval::new(day(3), day(7));
+        let raw = Interval::new(day(3).map(|t| t - 1), day(7).map(|t| t - 1));
+        let same = |api: &Api| -> anyhow::Result<()> {
+            for rq in [SubjectType::Character(5), SubjectType::Corporation(10)] {
+                assert_eq!(api.wins(rq, rollups)?, api.wins(rq, raw)?);
+                assert_eq!(api.losses(rq, rollups)?, api.losses(rq, raw)?);
+                assert_eq!(
+                    api.wins_ships(rq, rollups, PAGE)?.rows,
+                    api.wins_ships(rq, raw, PAGE)?.rows
+                );
+                assert_eq!(
+                    api.losses_ships(rq, rollups, PAGE)?.rows,
+                    api.losses_ships(rq, raw, PAGE)?.rows
+                );
+                assert_eq!(
+                    api.wins_systems(rq, rollups, PAGE)?.rows,
+                    api.wins_systems(rq, raw, PAGE)?.rows
+                );
+                assert_eq!(
+                    api.losses_systems(rq, rollups, PAGE)?.rows,
+                    api.losses_systems(rq, raw, PAGE)?.rows
+                );
+            }
+            Ok(())
+        };
+
+        same(&api)?;
+        assert_eq!(
+            api.wins(SubjectType::Character(5), rollups)?,
+            (5, Some(500))
+        );
+        assert_eq!(
+            api.wins_ships(SubjectType::Character(5), rollups, PAGE)?
+                .rows,
+            vec![(0, 4), (670, 1)]
+        );
+        assert_eq!(
+            api.wins_systems(SubjectType::Character(5), ALL, PAGE)?.rows,
+            vec![(1, 4), (2, 2)]
+        );
+
+        api.remove(&[3, 6])?;
+        same(&api)?;
+        assert_eq!(
+            api.wins(SubjectType::Character(5), rollups)?,
+            (2, Some(200))
+        );
+        assert_eq!(
+            api.losses(SubjectType::Corporation(10), ALL)?,
+            (3, Some(700))
+        );
+
+        assert_eq!(api.rebuild_rollups()?, 3);
+        same(&api)?;
+        assert_eq!(api.wins(SubjectType::Character(5), ALL)?, (3, Some(300)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn names() -> anyhow::Result<()> {
+        use crate::common::{Category, Names};
+
+        let api = create_api()?;
+        generate_killmails(&api, 2)?;
+        assert_eq!(
+            api.stored_ids()?,
+            vec![1, 2, 3, 10, 20, 30, 42, 100, 200, 300, 1000, 2000, 3000]
+        );
+
+        let location = Location {
+            system_id: 1,
+            constellation_id: 5000,
+            region_id: 6000,
+        };
+        assert_eq!(api.save_locations(&[location])?, 1);
+        assert_eq!(
+            api.stored_ids()?,
+            vec![1, 2, 3, 10, 20, 30, 42, 100, 200, 300, 1000, 2000, 3000, 5000, 6000]
+        );
+
+        let names = [
+            Names {
+                id: 2,
+                name: String::from("Pilot"),
+                category: Category::Character,
+            },
+            Names {
+                id: 42,
+                name: String::from("Rifter"),
+                category: Category::InventoryType,
+            },
+        ];
+        assert_eq!(api.save_names(&names)?, 2);
+        assert_eq!(api.save_names(&names[1..])?, 1);
+
+        let named = api.names(&[0, 2, 3, 42])?;
+        assert_eq!(named.len(), 2);
+        assert_eq!(named[&2], "Pilot");
+        assert_eq!(named[&42], "Rifter");
+
+        let day = chrono::Duration::days(1);
+        assert_eq!(api.unnamed(&[0, 2, 3, 42], day)?, vec![3]);
+        assert_eq!(
+            api.unnamed(&[0, 2, 3, 42], chrono::Duration::zero() - day)?,
+            vec![2, 3, 42]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn wars() -> anyhow::Result<()> {
+        let api = create_api()?;
+        let value = |total_value: f64| {
+            Some(killmails::zkb::Zkb {
+                hash: "a0b1c2d3e4f5".to_owned(),
+                total_value,
+                labels: Vec::new(),
+                ..Default::default()
+            })
+        };
+
+        let mut killmail = create_killmail(2);
+        killmail.war_id = Some(7);
+        killmail.attackers = vec![create_attacker(5)];
+        killmail.zkb = value(100.0);
+        api.save(&killmail)?;
+
+        let mut killmail = create_killmail(3);
+        killmail.war_id = Some(7);
+        killmail.attackers = vec![create_attacker(5), create_attacker(6)];
+        killmail.zkb = value(50.0);
+        api.save(&killmail)?;
+
+        let mut killmail = create_killmail(4);
+        killmail.war_id = Some(7);
+        killmail.victim.corporation_id = Some(60);
+        killmail.victim.alliance_id = None;
+        killmail.attackers = vec![create_attacker(2)];
+        api.save(&killmail)?;
+
+        let mut killmail = create_killmail(5);
+        killmail.attackers = vec![create_attacker(5)];
+        api.save(&killmail)?;
+
+        assert_eq!(api.war_kills(7, &[10], ALL)?, (2, Some(150.0)));
+        assert_eq!(api.war_kills(7, &[60], ALL)?, (1, None));
+        assert_eq!(api.war_kills(8, &[10], ALL)?, (0, None));
+
+        assert_eq!(
+            api.war_pilots(7, &[10], ALL, PAGE)?.rows,
+            vec![(5, 2), (6, 1)]
+        );
+        assert_eq!(api.war_pilots(7, &[60], ALL, PAGE)?.rows, vec![(2, 1)]);
+
+        let day = |d: u32| {
+            chrono::NaiveDate::from_ymd_opt(2024, 8, d)
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+                .map(|time| time.and_utc().timestamp())
+                .unwrap_or_default()
+        };
+        assert_eq!(
+            api.war_timeline(7, &[10], ALL)?,
+            vec![(day(2), 1), (day(3), 1)]
+        );
+        assert_eq!(
+            api.war_timeline(7, &[10], Interval::new(Some(day(3)), None))?,
+            vec![(day(3), 1)]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn war_sides() -> anyhow::Result<()> {
+        use crate::war::{Ally, Party, War};
+
+        let api = create_api()?;
+        let day = chrono::Duration::days(1);
+        assert!(api.war_sides(7, day)?.is_empty());
+
+        let mut war = War {
+            id: 7,
+            aggressor: Party {
+                alliance_id: Some(500),
+                ..Default::default()
+            },
+            defender: Party {
+                corporation_id: Some(100),
+                ..Default::default()
+            },
+            allies: Some(vec![Ally {
+                alliance_id: Some(300),
+                corporation_id: None,
+            }]),
+            ..Default::default()
+        };
+        assert_eq!(api.save_war_sides(&war)?, 3);
+        assert_eq!(
+            api.war_sides(7, day)?,
+            vec![(100, false), (300, false), (500, true)]
+        );
+        assert!(api.war_sides(7, chrono::Duration::zero() - day)?.is_empty());
+        assert!(api.war_sides(8, day)?.is_empty());
+
+        war.allies = None;
+        assert_eq!(api.save_war_sides(&war)?, 2);
+        assert_eq!(api.war_sides(7, day)?, vec![(100, false), (500, true)]);
+
+        Ok(())
+    }
 }
@@ -0,0 +1,30 @@
+use crate::common;
+use diesel::prelude::*;
+
+#[derive(Queryable, Selectable, Insertable, AsChangeset)]
+#[diesel(table_name = crate::schema::names, primary_key(id))]
+#[cfg_attr(feature = "postgres", diesel(check_for_backend(diesel::pg::Pg)))]
+#[cfg_attr(
+    not(feature = "postgres"),
+    diesel(check_for_backend(diesel::sqlite::Sqlite))
+)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Default)]
+pub struct Name {
+    pub id: i32,
+    pub category: String,
+    pub name: String,
+    pub fetched_at: i64,
+}
+impl From<(&common::Names, i64)> for Name {
+    fn from((names, fetched_at): (&common::Names, i64)) -> Self {
+        Name {
+            id: names.id,
+            category: serde_json::to_value(&names.category)
+                .ok()
+                .and_then(|category| category.as_str().map(String::from))
+                .unwrap_or_default(),
+            name: names.name.clone(),
+            fetched_at,
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use diesel::connection::SimpleConnection;
+use diesel::r2d2::{ConnectionManager, CustomizeConnection};
+
+#[cfg(feature = "postgres")]
+pub type DbConnection = diesel::pg::PgConnection;
+#[cfg(not(feature = "postgres"))]
+pub type DbConnection = diesel::sqlite::SqliteConnection;
+
+pub type Pool = diesel::r2d2::Pool<ConnectionManager<DbConnection>>;
+pub type PooledConnection = diesel::r2d2::PooledConnection<ConnectionManager<DbConnection>>;
+
+#[cfg(not(feature = "postgres"))]
+const BUSY_TIMEOUT: u32 = 5_000;
+
+#[derive(Debug, Clone, Copy)]
+struct Session {
+    readonly: bool,
+}
+impl Session {
+    #[cfg(not(feature = "postgres"))]
+    fn setup(&self) -> String {
+        if self.readonly {
+            format!("PRAGMA busy_timeout = {BUSY_TIMEOUT}; PRAGMA query_only = ON;")
+        } else {
+            format!("PRAGMA busy_timeout = {BUSY_TIMEOUT}; PRAGMA journal_mode = WAL; PRAGMA synchronous = NORMAL;")
+        }
+    }
+
+    #[cfg(feature = "postgres")]
+    fn setup(&self) -> String {
+        if self.readonly {
+            String::from("SET default_transaction_read_only = on;")
+        } else {
+            String::new()
+        }
+    }
+}
+impl CustomizeConnection<DbConnection, diesel::r2d2::Error> for Session {
+    fn on_acquire(&self, conn: &mut DbConnection) -> Result<(), diesel::r2d2::Error> {
+        let setup = self.setup();
+        if setup.is_empty() {
+            return Ok(());
+        }
+        conn.batch_execute(&setup)
+            .map_err(diesel::r2d2::Error::QueryError)
+    }
+}
+
+pub fn writer(uri: &str) -> anyhow::Result<Pool> {
+    build(uri, 1, false)
+}
+
+pub fn readers(uri: &str, size: u32) -> anyhow::Result<Pool> {
+    build(uri, size, true)
+}
+
+fn build(uri: &str, size: u32, readonly: bool) -> anyhow::Result<Pool> {
+    Pool::builder()
+        .max_size(size)
+        .connection_customizer(Box::new(Session { readonly }))
+        .build(ConnectionManager::<DbConnection>::new(uri))
+        .map_err(|e| anyhow::anyhow!("{e}"))
+}
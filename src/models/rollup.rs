@@ -0,0 +1,461 @@
+use crate::models::api::{Page, Paged};
+use crate::models::attacker::Attacker;
+use crate::models::killmail::Killmail;
+use crate::models::pool::DbConnection;
+use crate::models::victim::Victim;
+use crate::schema;
+
+use diesel::dsl::sum;
+use diesel::prelude::*;
+use diesel::upsert::excluded;
+use std::collections::HashMap;
+
+pub const DAY: i64 = 86_400;
+
+pub const CHARACTER: i32 = 1;
+pub const CORPORATION: i32 = 2;
+pub const ALLIANCE: i32 = 3;
+pub const FACTION: i32 = 4;
+
+pub type Entity = (i32, i32);
+
+pub fn day(timestamp: i64) -> i64 {
+    timestamp - timestamp.rem_euclid(DAY)
+}
+
+fn entities(character: i32, corporation: i32, alliance: i32, faction: i32) -> Vec<Entity> {
+    [
+        (CHARACTER, character),
+        (CORPORATION, corporation),
+        (ALLIANCE, alliance),
+        (FACTION, faction),
+    ]
+    .into_iter()
+    .filter(|(_, id)| *id != 0)
+    .collect()
+}
+
+#[derive(Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::rollups)]
+#[cfg_attr(feature = "postgres", diesel(check_for_backend(diesel::pg::Pg)))]
+#[cfg_attr(
+    not(feature = "postgres"),
+    diesel(check_for_backend(diesel::sqlite::Sqlite))
+)]
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Rollup {
+    pub entity_type: i32,
+    pub entity_id: i32,
+    pub day: i64,
+    pub kills: i32,
+    pub damage_done: i64,
+    pub losses: i32,
+    pub damage_taken: i64,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::rollup_ships)]
+struct RollupShip {
+    entity_type: i32,
+    entity_id: i32,
+    day: i64,
+    ship_type_id: i32,
+    kills: i32,
+    losses: i32,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::rollup_systems)]
+struct RollupSystem {
+    entity_type: i32,
+    entity_id: i32,
+    day: i64,
+    solar_system_id: i32,
+    kills: i32,
+    losses: i32,
+}
+
+#[derive(Debug, Default)]
+pub struct Rollups {
+    totals: HashMap<(Entity, i64), Rollup>,
+    ships: HashMap<(Entity, i64, i32), (i32, i32)>,
+    systems: HashMap<(Entity, i64, i32), (i32, i32)>,
+}
+impl Rollups {
+    pub fn add(
+        &mut self,
+        killmail: &Killmail,
+        victim: Option<&Victim>,
+        attackers: &[Attacker],
+        sign: i32,
+    ) {
+        let day = day(killmail.killmail_timestamp);
+        let system = killmail.solar_system_id;
+        for attacker in attackers {
+            for entity in entities(
+                attacker.character_id,
+                attacker.corporation_id,
+                attacker.alliance_id,
+                attacker.faction_id,
+            ) {
+                let total = self.total(entity, day);
+                total.kills += sign;
+                total.damage_done += i64::from(sign) * i64::from(attacker.damage_done);
+                self.ships
+                    .entry((entity, day, attacker.ship_type_id))
+                    .or_default()
+                    .0 += sign;
+                self.systems.entry((entity, day, system)).or_default().0 += sign;
+            }
+        }
+        if let Some(victim) = victim {
+            for entity in entities(
+                victim.character_id,
+                victim.corporation_id,
+                victim.alliance_id,
+                victim.faction_id,
+            ) {
+                let total = self.total(entity, day);
+                total.losses += sign;
+                total.damage_taken += i64::from(sign) * i64::from(victim.damage_taken);
+                self.ships
+                    .entry((entity, day, victim.ship_type_id))
+                    .or_default()
+                    .1 += sign;
+                self.systems.entry((entity, day, system)).or_default().1 += sign;
+            }
+        }
+    }
+
+    fn total(&mut self, (entity_type, entity_id): Entity, day: i64) -> &mut Rollup {
+        self.totals
+            .entry(((entity_type, entity_id), day))
+            .or_insert_with(|| Rollup {
+                entity_type,
+                entity_id,
+                day,
+                ..Default::default()
+            })
+    }
+
+    pub fn apply(self, conn: &mut DbConnection) -> QueryResult<()> {
+        use schema::{rollup_ships, rollup_systems, rollups};
+
+        for total in self.totals.into_values() {
+            diesel::insert_into(rollups::table)
+                .values(&total)
+                .on_conflict((rollups::entity_type, rollups::entity_id, rollups::day))
+                .do_update()
+                .set((
+                    rollups::kills.eq(rollups::kills + excluded(rollups::kills)),
+                    rollups::damage_done.eq(rollups::damage_done + excluded(rollups::damage_done)),
+                    rollups::losses.eq(rollups::losses + excluded(rollups::losses)),
+                    rollups::damage_taken
+                        .eq(rollups::damage_taken + excluded(rollups::damage_taken)),
+                ))
+                .execute(conn)?;
+        }
+        for (((entity_type, entity_id), day, ship_type_id), (kills, losses)) in self.ships {
+            diesel::insert_into(rollup_ships::table)
+                .values(RollupShip {
+                    entity_type,
+                    entity_id,
+                    day,
+                    ship_type_id,
+                    kills,
+                    losses,
+                })
+                .on_conflict((
+                    rollup_ships::entity_type,
+                    rollup_ships::entity_id,
+                    rollup_ships::day,
+                    rollup_ships::ship_type_id,
+                ))
+                .do_update()
+                .set((
+                    rollup_ships::kills.eq(rollup_ships::kills + excluded(rollup_ships::kills)),
+                    rollup_ships::losses.eq(rollup_ships::losses + excluded(rollup_ships::losses)),
+                ))
+                .execute(conn)?;
+        }
+        for (((entity_type, entity_id), day, solar_system_id), (kills, losses)) in self.systems {
+            diesel::insert_into(rollup_systems::table)
+                .values(RollupSystem {
+                    entity_type,
+                    entity_id,
+                    day,
+                    solar_system_id,
+                    kills,
+                    losses,
+                })
+                .on_conflict((
+                    rollup_systems::entity_type,
+                    rollup_systems::entity_id,
+                    rollup_systems::day,
+                    rollup_systems::solar_system_id,
+                ))
+                .do_update()
+                .set((
+                    rollup_systems::kills
+                        .eq(rollup_systems::kills + excluded(rollup_systems::kills)),
+                    rollup_systems::losses
+                        .eq(rollup_systems::losses + excluded(rollup_systems::losses)),
+                ))
+                .execute(conn)?;
+        }
+        Ok(())
+    }
+}
+
+pub fn prune(conn: &mut DbConnection) -> QueryResult<usize> {
+    use schema::{rollup_ships, rollup_systems, rollups};
+
+    let mut count = diesel::delete(
+        rollups::table
+            .filter(rollups::kills.eq(0))
+            .filter(rollups::losses.eq(0)),
+    )
+    .execute(conn)?;
+    count += diesel::delete(
+        rollup_ships::table
+            .filter(rollup_ships::kills.eq(0))
+            .filter(rollup_ships::losses.eq(0)),
+    )
+    .execute(conn)?;
+    count += diesel::delete(
+        rollup_systems::table
+            .filter(rollup_systems::kills.eq(0))
+            .filter(rollup_systems::losses.eq(0)),
+    )
+    .execute(conn)?;
+    Ok(count)
+}
+
+pub fn clear(conn: &mut DbConnection) -> QueryResult<usize> {
+    let mut count = diesel::delete(schema::rollups::table).execute(conn)?;
+    count += diesel::delete(schema::rollup_ships::table).execute(conn)?;
+    count += diesel::delete(schema::rollup_systems::table).execute(conn)?;
+    Ok(count)
+}
+
+fn totals(
+    conn: &mut DbConnection,
+    (kind, id): Entity,
+    start: i64,
+    end: i64,
+) -> QueryResult<Vec<Rollup>> {
+    use schema::rollups::dsl::*;
+
+    rollups
+        .filter(entity_type.eq(kind))
+        .filter(entity_id.eq(id))
+        .filter(day.ge(start))
+        .filter(day.lt(end))
+        .select(Rollup::as_select())
+        .load::<Rollup>(conn)
+}
+
+pub fn wins(
+    conn: &mut DbConnection,
+    entity: Entity,
+    start: i64,
+    end: i64,
+) -> QueryResult<(i64, Option<i64>)> {
+    let totals = totals(conn, entity, start, end)?;
+    let kills = totals
+        .iter()
+        .map(|total| i64::from(total.kills))
+        .sum::<i64>();
+    let damage = totals.iter().map(|total| total.damage_done).sum::<i64>();
+    Ok((kills, (kills > 0).then_some(damage)))
+}
+
+pub fn losses(
+    conn: &mut DbConnection,
+    entity: Entity,
+    start: i64,
+    end: i64,
+) -> QueryResult<(i64, Option<i64>)> {
+    let totals = totals(conn, entity, start, end)?;
+    let losses = totals
+        .iter()
+        .map(|total| i64::from(total.losses))
+        .sum::<i64>();
+    let damage = totals.iter().map(|total| total.damage_taken).sum::<i64>();
+    Ok((losses, (losses > 0).then_some(damage)))
+}
+
+fn counts(paged: Paged<(i32, Option<i64>)>) -> Paged<(i32, i64)> {
+    Paged {
+        rows: paged
+            .rows
+            .into_iter()
+            .map(|(id, count)| (id, count.unwrap_or_default()))
+            .collect(),
+        total: paged.total,
+    }
+}
+
+pub fn wins_ships(
+    conn: &mut DbConnection,
+    (kind, id): Entity,
+    start: i64,
+    end: i64,
+    page: Page,
+) -> QueryResult<Paged<(i32, i64)>> {
+    use schema::rollup_ships::dsl::*;
+
+    let query = || {
+        rollup_ships
+            .filter(entity_type.eq(kind))
+            .filter(entity_id.eq(id))
+            .filter(day.ge(start))
+            .filter(day.lt(end))
+            .filter(kills.gt(0))
+    };
+    Ok(counts(paged_groups!(
+        conn,
+        query,
+        ship_type_id,
+        sum(kills),
+        page
+    )))
+}
+
+pub fn losses_ships(
+    conn: &mut DbConnection,
+    (kind, id): Entity,
+    start: i64,
+    end: i64,
+    page: Page,
+) -> QueryResult<Paged<(i32, i64)>> {
+    use schema::rollup_ships::dsl::*;
+
+    let query = || {
+        rollup_ships
+            .filter(entity_type.eq(kind))
+            .filter(entity_id.eq(id))
+            .filter(day.ge(start))
+            .filter(day.lt(end))
+            .filter(losses.gt(0))
+    };
+    Ok(counts(paged_groups!(
+        conn,
+        query,
+        ship_type_id,
+        sum(losses),
+        page
+    )))
+}
+
+pub fn wins_systems(
+    conn: &mut DbConnection,
+    (kind, id): Entity,
+    start: i64,
+    end: i64,
+    page: Page,
+) -> QueryResult<Paged<(i32, i64)>> {
+    use schema::rollup_systems::dsl::*;
+
+    let query = || {
+        rollup_systems
+            .filter(entity_type.eq(kind))
+            .filter(entity_id.eq(id))
+            .filter(day.ge(start))
+            .filter(day.lt(end))
+            .filter(kills.gt(0))
+    };
+    Ok(counts(paged_groups!(
+        conn,
+        query,
+        solar_system_id,
+        sum(kills),
+        page
+    )))
+}
+
+pub fn losses_systems(
+    conn: &mut DbConnection,
+    (kind, id): Entity,
+    start: i64,
+    end: i64,
+    page: Page,
+) -> QueryResult<Paged<(i32, i64)>> {
+    use schema::rollup_systems::dsl::*;
+
+    let query = || {
+        rollup_systems
+            .filter(entity_type.eq(kind))
+            .filter(entity_id.eq(id))
+            .filter(day.ge(start))
+            .filter(day.lt(end))
+            .filter(losses.gt(0))
+    };
+    Ok(counts(paged_groups!(
+        conn,
+        query,
+        solar_system_id,
+        sum(losses),
+        page
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attacker(character_id: i32, corporation_id: i32, ship_type_id: i32) -> Attacker {
+        Attacker {
+            character_id,
+            corporation_id,
+            damage_done: 100,
+            ship_type_id,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn day() {
+        assert_eq!(super::day(1724730850), 1724716800);
+        assert_eq!(super::day(1724716800), 1724716800);
+        assert_eq!(super::day(-1), -DAY);
+    }
+
+    #[test]
+    fn add() {
+        let killmail = Killmail {
+            killmail_id: 1,
+            solar_system_id: 30000142,
+            killmail_timestamp: 1724730850,
+            ..Default::default()
+        };
+        let victim = Victim {
+            killmail_id: 1,
+            character_id: 9,
+            damage_taken: 200,
+            ship_type_id: 670,
+            ..Default::default()
+        };
+        let attackers = vec![attacker(7, 70, 600), attacker(8, 70, 601)];
+
+        let mut rollups = Rollups::default();
+        rollups.add(&killmail, Some(&victim), &attackers, 1);
+
+        let day = 1724716800;
+        assert_eq!(rollups.totals.len(), 4);
+        let corporation = &rollups.totals[&((CORPORATION, 70), day)];
+        assert_eq!((corporation.kills, corporation.damage_done), (2, 200));
+        let character = &rollups.totals[&((CHARACTER, 9), day)];
+        assert_eq!((character.losses, character.damage_taken), (1, 200));
+        assert_eq!(rollups.ships[&((CORPORATION, 70), day, 601)], (1, 0));
+        assert_eq!(rollups.ships[&((CHARACTER, 9), day, 670)], (0, 1));
+        assert_eq!(rollups.systems[&((CORPORATION, 70), day, 30000142)], (2, 0));
+
+        rollups.add(&killmail, Some(&victim), &attackers, -1);
+        assert!(rollups.totals.values().all(|total| total.kills == 0
+            && total.losses == 0
+            && total.damage_done == 0
+            && total.damage_taken == 0));
+        assert!(rollups.ships.values().all(|count| *count == (0, 0)));
+    }
+}
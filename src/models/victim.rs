@@ -1,3 +1,4 @@
+use crate::common::Position;
 use crate::killmails;
 use diesel::prelude::*;
 
@@ -5,7 +6,11 @@ use super::as_option;
 
 #[derive(Queryable, Selectable, Insertable)]
 #[diesel(table_name = crate::schema::victims)]
-#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+#[cfg_attr(feature = "postgres", diesel(check_for_backend(diesel::pg::Pg)))]
+#[cfg_attr(
+    not(feature = "postgres"),
+    diesel(check_for_backend(diesel::sqlite::Sqlite))
+)]
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Default)]
 pub struct Victim {
     pub killmail_id: i32,
@@ -15,6 +20,9 @@ pub struct Victim {
     pub faction_id: i32,
     pub damage_taken: i32,
     pub ship_type_id: i32,
+    pub position_x: Option<f64>,
+    pub position_y: Option<f64>,
+    pub position_z: Option<f64>,
 }
 impl From<(i32, &killmails::Victim)> for Victim {
     fn from((id, victim): (i32, &killmails::Victim)) -> Self {
@@ -26,6 +34,9 @@ impl From<(i32, &killmails::Victim)> for Victim {
             faction_id: victim.faction_id.unwrap_or_default(),
             damage_taken: victim.damage_taken,
             ship_type_id: victim.ship_type_id,
+            position_x: victim.position.as_ref().map(|p| p.x),
+            position_y: victim.position.as_ref().map(|p| p.y),
+            position_z: victim.position.as_ref().map(|p| p.z),
         }
     }
 }
@@ -39,8 +50,11 @@ impl Into<killmails::Victim> for Victim {
             faction_id: as_option(self.faction_id),
             damage_taken: self.damage_taken,
             ship_type_id: self.ship_type_id,
-            position: None,
-            items: None
+            position: match (self.position_x, self.position_y, self.position_z) {
+                (Some(x), Some(y), Some(z)) => Some(Position::new(x, y, z)),
+                _ => None,
+            },
+            items: None,
         }
     }
 }
@@ -0,0 +1,32 @@
+use crate::war;
+use diesel::prelude::*;
+
+#[derive(Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::war_sides, primary_key(war_id, entity_id))]
+#[cfg_attr(feature = "postgres", diesel(check_for_backend(diesel::pg::Pg)))]
+#[cfg_attr(
+    not(feature = "postgres"),
+    diesel(check_for_backend(diesel::sqlite::Sqlite))
+)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Default)]
+pub struct WarSide {
+    pub war_id: i32,
+    pub entity_id: i32,
+    pub aggressor: bool,
+    pub fetched_at: i64,
+}
+impl WarSide {
+    pub fn sides(war: &war::War, fetched_at: i64) -> Vec<Self> {
+        let side = |entity_id, aggressor| WarSide {
+            war_id: war.id,
+            entity_id,
+            aggressor,
+            fetched_at,
+        };
+        war.aggressors()
+            .into_iter()
+            .map(|id| side(id, true))
+            .chain(war.defenders().into_iter().map(|id| side(id, false)))
+            .collect()
+    }
+}
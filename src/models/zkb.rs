@@ -0,0 +1,61 @@
+use crate::killmails;
+use diesel::prelude::*;
+
+const SEPARATOR: &str = ",";
+
+#[derive(Queryable, Selectable, Insertable, AsChangeset)]
+#[diesel(table_name = crate::schema::zkb, primary_key(killmail_id))]
+#[cfg_attr(feature = "postgres", diesel(check_for_backend(diesel::pg::Pg)))]
+#[cfg_attr(
+    not(feature = "postgres"),
+    diesel(check_for_backend(diesel::sqlite::Sqlite))
+)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Default)]
+pub struct Zkb {
+    pub killmail_id: i32,
+    pub hash: String,
+    pub total_value: f64,
+    pub fitted_value: f64,
+    pub dropped_value: f64,
+    pub points: i32,
+    pub npc: bool,
+    pub solo: bool,
+    pub awox: bool,
+    pub labels: String,
+}
+impl From<(i32, &killmails::Zkb)> for Zkb {
+    fn from((id, zkb): (i32, &killmails::Zkb)) -> Self {
+        Zkb {
+            killmail_id: id,
+            hash: zkb.hash.clone(),
+            total_value: zkb.total_value,
+            fitted_value: zkb.fitted_value,
+            dropped_value: zkb.dropped_value,
+            points: zkb.points,
+            npc: zkb.npc,
+            solo: zkb.solo,
+            awox: zkb.awox,
+            labels: zkb.labels.join(SEPARATOR),
+        }
+    }
+}
+impl From<Zkb> for killmails::Zkb {
+    fn from(zkb: Zkb) -> Self {
+        killmails::Zkb {
+            hash: zkb.hash,
+            total_value: zkb.total_value,
+            fitted_value: zkb.fitted_value,
+            dropped_value: zkb.dropped_value,
+            points: zkb.points,
+            npc: zkb.npc,
+            solo: zkb.solo,
+            awox: zkb.awox,
+            labels: zkb
+                .labels
+                .split(SEPARATOR)
+                .filter(|label| !label.is_empty())
+                .map(String::from)
+                .collect(),
+        }
+    }
+}
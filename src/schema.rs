@@ -1,8 +1,9 @@
 // @generated automatically by Diesel CLI.
 
 diesel::table! {
-    attackers (killmail_id, character_id, corporation_id, alliance_id, faction_id) {
+    attackers (killmail_id, attacker_index) {
         killmail_id -> Integer,
+        attacker_index -> Integer,
         character_id -> Integer,
         corporation_id -> Integer,
         alliance_id -> Integer,
@@ -15,6 +16,19 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    items (killmail_id, item_index) {
+        killmail_id -> Integer,
+        item_index -> Integer,
+        parent_index -> Nullable<Integer>,
+        flag -> Integer,
+        item_type_id -> Integer,
+        quantity_destroyed -> Nullable<BigInt>,
+        quantity_dropped -> Nullable<BigInt>,
+        singleton -> Integer,
+    }
+}
+
 diesel::table! {
     killmails (killmail_id) {
         killmail_id -> Integer,
@@ -22,11 +36,63 @@ diesel::table! {
         solar_system_id -> Integer,
         moon_id -> Nullable<Integer>,
         war_id -> Nullable<Integer>,
+        killmail_timestamp -> BigInt,
+    }
+}
+
+diesel::table! {
+    locations (system_id) {
+        system_id -> Integer,
+        constellation_id -> Integer,
+        region_id -> Integer,
+    }
+}
+
+diesel::table! {
+    names (id) {
+        id -> Integer,
+        category -> Text,
+        name -> Text,
+        fetched_at -> BigInt,
+    }
+}
+
+diesel::table! {
+    rollup_ships (entity_type, entity_id, day, ship_type_id) {
+        entity_type -> Integer,
+        entity_id -> Integer,
+        day -> BigInt,
+        ship_type_id -> Integer,
+        kills -> Integer,
+        losses -> Integer,
     }
 }
 
 diesel::table! {
-    victims (killmail_id, character_id, corporation_id, alliance_id, faction_id) {
+    rollup_systems (entity_type, entity_id, day, solar_system_id) {
+        entity_type -> Integer,
+        entity_id -> Integer,
+        day -> BigInt,
+        solar_system_id -> Integer,
+        kills -> Integer,
+        losses -> Integer,
+    }
+}
+
+diesel::table! {
+    rollups (entity_type, entity_id, day) {
+        entity_type -> Integer,
+        entity_id -> Integer,
+        day -> BigInt,
+        kills -> Integer,
+        damage_done -> BigInt,
+        losses -> Integer,
+        damage_taken -> BigInt,
+    }
+}
+
+diesel::table! {
+    victims (killmail_id) {
         killmail_id -> Integer,
         character_id -> Integer,
         corporation_id -> Integer,
@@ -34,14 +100,51 @@ diesel::table! {
         faction_id -> Integer,
         damage_taken -> Integer,
         ship_type_id -> Integer,
+        position_x -> Nullable<Double>,
+        position_y -> Nullable<Double>,
+        position_z -> Nullable<Double>,
+    }
+}
+
+diesel::table! {
+    war_sides (war_id, entity_id) {
+        war_id -> Integer,
+        entity_id -> Integer,
+        aggressor -> Bool,
+        fetched_at -> BigInt,
+    }
+}
+
+diesel::table! {
+    zkb (killmail_id) {
+        killmail_id -> Integer,
+        hash -> Text,
+        total_value -> Double,
+        fitted_value -> Double,
+        dropped_value -> Double,
+        points -> Integer,
+        npc -> Bool,
+        solo -> Bool,
+        awox -> Bool,
+        labels -> Text,
     }
 }
 
 diesel::joinable!(attackers -> killmails (killmail_id));
+diesel::joinable!(items -> killmails (killmail_id));
 diesel::joinable!(victims -> killmails (killmail_id));
+diesel::joinable!(zkb -> killmails (killmail_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
     attackers,
+    items,
     killmails,
+    locations,
+    names,
+    rollup_ships,
+    rollup_systems,
+    rollups,
     victims,
+    war_sides,
+    zkb,
 );
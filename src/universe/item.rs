@@ -1,8 +1,8 @@
-use crate::universe::utils;
 use crate::esi::api::Uid;
 use crate::esi::api::Uri;
 use crate::esi::PARAM;
 use crate::esi::UNIVERSE;
+use crate::universe::utils;
 use std::fmt;
 
 use anyhow::anyhow;
@@ -41,6 +41,16 @@ pub struct Type {
     pub dogma_attributes: Option<Vec<DogmaAttributes>>,
     pub dogma_effects: Option<Vec<DogmaEffects>>,
 }
+impl Type {
+    pub fn attribute(&self, attribute_id: i32) -> Option<f32> {
+        self.dogma_attributes.as_ref().and_then(|attributes| {
+            attributes
+                .iter()
+                .find(|attribute| attribute.attribute_id == attribute_id)
+                .map(|attribute| attribute.value)
+        })
+    }
+}
 impl fmt::Display for Type {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         writeln!(f, "{}: {}", self.type_id, self.name)?;
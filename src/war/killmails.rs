@@ -0,0 +1,55 @@
+use crate::esi::api::Uid;
+use crate::esi::api::Uri;
+use crate::esi::PARAM;
+use crate::esi::WARS;
+
+use anyhow::anyhow;
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
+pub struct WarKillmail {
+    pub killmail_id: i32,
+    pub killmail_hash: String,
+}
+impl Uri for WarKillmail {
+    fn uri(id: &Uid) -> anyhow::Result<String> {
+        if let Uid::Id(id) = id {
+            Ok(format!("{WARS}/{id}/killmails/?{PARAM}"))
+        } else {
+            Err(anyhow!("Expected Uid::Id(i32)"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const JSON: &str = r##"
+    [
+        {
+            "killmail_hash": "8eef5e8fb6b88fe3407c489df33822b2e3b57a5e",
+            "killmail_id": 120480909
+        },
+        {
+            "killmail_hash": "b41ccb498ece33d64019f64c0db392aa3aa701fb",
+            "killmail_id": 120481014
+        }
+    ]"##;
+
+    #[test]
+    fn parse() -> anyhow::Result<()> {
+        let killmails = serde_json::from_str::<Vec<WarKillmail>>(JSON)?;
+
+        assert_eq!(killmails.len(), 2);
+        assert_eq!(killmails[0].killmail_id, 120480909);
+        assert_eq!(
+            &killmails[1].killmail_hash,
+            "b41ccb498ece33d64019f64c0db392aa3aa701fb"
+        );
+        assert_eq!(
+            WarKillmail::uri(&Uid::Id(746215))?,
+            format!("{WARS}/746215/killmails/?{PARAM}")
+        );
+        Ok(())
+    }
+}
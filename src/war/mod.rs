@@ -0,0 +1,5 @@
+pub mod killmails;
+pub mod model;
+
+pub use killmails::WarKillmail;
+pub use model::{Ally, Party, War};
@@ -0,0 +1,120 @@
+use crate::esi::api::Uid;
+use crate::esi::api::Uri;
+use crate::esi::PARAM;
+use crate::esi::WARS;
+
+use anyhow::anyhow;
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
+pub struct Party {
+    pub alliance_id: Option<i32>,
+    pub corporation_id: Option<i32>,
+    pub isk_destroyed: f64,
+    pub ships_killed: i32,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
+pub struct Ally {
+    pub alliance_id: Option<i32>,
+    pub corporation_id: Option<i32>,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
+pub struct War {
+    pub id: i32,
+    pub aggressor: Party,
+    pub defender: Party,
+    pub allies: Option<Vec<Ally>>,
+    pub declared: String,
+    pub started: Option<String>,
+    pub finished: Option<String>,
+    pub retracted: Option<String>,
+    pub mutual: bool,
+    pub open_for_allies: bool,
+}
+impl War {
+    pub fn aggressors(&self) -> Vec<i32> {
+        [self.aggressor.alliance_id, self.aggressor.corporation_id]
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+
+    pub fn defenders(&self) -> Vec<i32> {
+        let allies = self.allies.iter().flatten();
+        [self.defender.alliance_id, self.defender.corporation_id]
+            .into_iter()
+            .chain(allies.flat_map(|ally| [ally.alliance_id, ally.corporation_id]))
+            .flatten()
+            .collect()
+    }
+}
+impl Uri for War {
+    fn uri(id: &Uid) -> anyhow::Result<String> {
+        if let Uid::Id(id) = id {
+            Ok(format!("{WARS}/{id}/?{PARAM}"))
+        } else {
+            Err(anyhow!("Expected Uid::Id(i32)"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const JSON: &str = r##"
+    {
+        "aggressor": {
+            "alliance_id": 99011990,
+            "isk_destroyed": 2563104155.33,
+            "ships_killed": 12
+        },
+        "allies": [
+            { "corporation_id": 98681714 },
+            { "alliance_id": 99012162 }
+        ],
+        "declared": "2024-09-01T10:15:00Z",
+        "defender": {
+            "corporation_id": 98316235,
+            "isk_destroyed": 140000000.0,
+            "ships_killed": 2
+        },
+        "id": 746215,
+        "mutual": false,
+        "open_for_allies": true,
+        "started": "2024-09-02T10:15:00Z"
+    }"##;
+
+    #[test]
+    fn parse() -> anyhow::Result<()> {
+        let war = serde_json::from_str::<War>(JSON)?;
+
+        assert_eq!(war.id, 746215);
+        assert_eq!(war.aggressor.alliance_id, Some(99011990));
+        assert_eq!(war.aggressor.corporation_id, None);
+        assert_eq!(war.aggressor.ships_killed, 12);
+        assert_eq!(war.defender.corporation_id, Some(98316235));
+        assert_eq!(war.allies.as_ref().map(Vec::len), Some(2));
+        assert_eq!(&war.declared, "2024-09-01T10:15:00Z");
+        assert_eq!(war.started, Some(String::from("2024-09-02T10:15:00Z")));
+        assert_eq!(war.finished, None);
+        assert_eq!(war.retracted, None);
+        assert!(!war.mutual);
+        assert!(war.open_for_allies);
+        Ok(())
+    }
+
+    #[test]
+    fn sides() -> anyhow::Result<()> {
+        let war = serde_json::from_str::<War>(JSON)?;
+
+        assert_eq!(war.aggressors(), vec![99011990]);
+        assert_eq!(war.defenders(), vec![98316235, 98681714, 99012162]);
+        assert_eq!(
+            War::uri(&Uid::Id(746215))?,
+            format!("{WARS}/746215/?{PARAM}")
+        );
+        Ok(())
+    }
+}